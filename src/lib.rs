@@ -1,13 +1,22 @@
 //! # Bevy Restrict
 //! Utilities for restricting the use of certain bevy features
-use std::marker::PhantomData;
+use std::{
+    any::TypeId,
+    marker::PhantomData,
+    ops::{Deref, Not},
+    sync::Arc,
+    time::Duration,
+};
 
 use bevy::{
     ecs::{
+        event::ManualEventReader,
         query::ReadOnlyWorldQuery,
         system::{EntityCommands, SystemParam},
     },
     prelude::*,
+    sprite::Anchor,
+    utils::{HashMap, HashSet},
 };
 
 #[cfg(test)]
@@ -15,15 +24,156 @@ mod tests;
 
 pub mod prelude {
     pub use super::{
-        entity_cleanup_system, marker_components, resource_cleanup_system, spawn_button,
-        spawn_default_system, square_sprite, state_resource_plugin_from_world,
-        state_resource_plugin_given, ButtonStyle, ClosurePlugin, EntityDespawner, EntitySpawner,
-        ResourceHandle, SquareSprite,
+        advance_game_time_system, button_click_event_system, button_debounce_system,
+        button_interaction_system, cleanup_bounds_from_window_system, closure_plugin,
+        collect_entities_system, conditional_entity_cleanup_system, death_cleanup_system,
+        despawn_all_system, despawn_where_system, entity_cleanup_system, entity_count_system,
+        event_entity_cleanup_system, fade_out_system, forbid_component_system, grid_to_world,
+        lifetime_system, marker_components, menu_navigation_system, offscreen_cleanup_system,
+        persistent_state_resource_plugin, rect_sprite, reset_entity, resource_absent,
+        resource_cleanup_system, resource_exists_condition, resource_reset_system,
+        respawn_on_resource_change_system, restyle_button, seeded_rng_plugin, set_button_disabled,
+        set_resource_system, spawn_button, spawn_button_grid, spawn_button_grid_with,
+        spawn_button_with, spawn_cooldown_system, spawn_default_system,
+        spawn_default_system_tracked, spawn_grid, spawn_square_sprite, spawn_ui_root,
+        square_sprite, square_sprite_border, square_sprite_from_palette, startup_closure_plugin,
+        state_entity_cleanup_plugin, state_resource_plugin_from_world, state_resource_plugin_given,
+        state_resource_plugin_given_many, state_resource_plugin_with, state_scoped_cleanup_system,
+        state_transition_button_system, state_visibility_plugin, swap_marker,
+        timed_entity_cleanup_system, toggle_state_plugin, with_lifetime, world_to_grid,
+        ButtonClicked, ButtonColors, ButtonDebounce, ButtonDisabled, ButtonMenu, ButtonStyle,
+        CleanupBounds, CleanupTimer, ClosurePlugin, CollectPlugin, CombinedClosurePlugin, Count,
+        CountTrackerPlugin, DespawnSet, DespawnSetPlugin, Died, EntityCount,
+        EntityCountLoggerPlugin, EntityDespawner, EntityEditor, EntityList, EntitySpawner, FadeOut,
+        ForbidPolicy, GameTime, Health, IndexedMarker, LastSpawned, Layer, Lifetime, MenuFocus,
+        OnceClosurePlugin, Palette, Paused, ReadOnlyRes, RectSprite, ResetGame, ResetPlugin,
+        Resettable, ResourceChangeCount, ResourceChangeLoggerPlugin, ResourceHandle,
+        ResourceSnapshot, ShouldCleanup, SingletonPlugin, SingletonPolicy, SpawnCooldown,
+        SpawnCounter, SpawnLimit, SquareSprite, SquareSpriteBuilder, StartupClosurePlugin,
+        StateScopePlugin, StateScoped, StateSetter, TrackedCount, TransientResourcePlugin,
+        Versioned,
     };
 }
 
 #[macro_export]
 macro_rules! marker_components {
+    (@check_derive Clone) => { $crate::marker_components!(@duplicate_derive Clone); };
+    (@check_derive Copy) => { $crate::marker_components!(@duplicate_derive Copy); };
+    (@check_derive Default) => { $crate::marker_components!(@duplicate_derive Default); };
+    (@check_derive Component) => { $crate::marker_components!(@duplicate_derive Component); };
+    (@check_derive Hash) => { $crate::marker_components!(@duplicate_derive Hash); };
+    (@check_derive PartialEq) => { $crate::marker_components!(@duplicate_derive PartialEq); };
+    (@check_derive Eq) => { $crate::marker_components!(@duplicate_derive Eq); };
+    (@check_derive PartialOrd) => { $crate::marker_components!(@duplicate_derive PartialOrd); };
+    (@check_derive Ord) => { $crate::marker_components!(@duplicate_derive Ord); };
+    (@check_derive $derive:ident) => {};
+    (@duplicate_derive $derive:ident) => {
+        compile_error!(concat!("`", stringify!($derive), "` is already derived by `marker_components!`"));
+    };
+    (@check_derives ($($derive:ident),* $(,)?)) => {
+        $($crate::marker_components!(@check_derive $derive);)*
+    };
+    (derive $derives:tt; $($(# $tt:tt)*$id:ident),*) => {
+        $crate::marker_components!(@check_derives $derives);
+        $crate::marker_components!($(#[derive $derives] $(# $tt)* $id),*);
+    };
+    (reflect: $($(# $tt:tt)*$id:ident),*) => {
+        $crate::marker_components!($(#[derive(::bevy::reflect::Reflect)] $(# $tt)* $id),*);
+
+        // `#[reflect(Component)]` would need `ReflectComponent` in scope at the call site,
+        // so the component data is registered explicitly instead
+        pub fn register_markers(app: &mut ::bevy::app::App) {
+            $(
+                app.register_type::<$id>()
+                    .register_type_data::<$id, ::bevy::ecs::reflect::ReflectComponent>();
+            )*
+        }
+    };
+    (enum $kind:ident; $($(# $tt:tt)*$id:ident),*) => {
+        $crate::marker_components!($($(# $tt)* $id),*);
+
+        /// Selects one of the markers at runtime, e.g. from loaded data
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub enum $kind {
+            $($id,)*
+        }
+
+        impl $kind {
+            pub const ALL: &'static [Self] = &[$(Self::$id,)*];
+
+            /// Insert the marker for this variant on `entity`
+            pub fn insert(self, entity: &mut ::bevy::ecs::system::EntityCommands) {
+                match self {
+                    $(Self::$id => {
+                        entity.insert($id);
+                    })*
+                }
+            }
+
+            /// Remove the marker for this variant from `entity`
+            pub fn remove(self, entity: &mut ::bevy::ecs::system::EntityCommands) {
+                match self {
+                    $(Self::$id => {
+                        entity.remove::<$id>();
+                    })*
+                }
+            }
+        }
+    };
+    (bundle $bundle:ident; $($(# $tt:tt)*$id:ident),*) => {
+        $crate::marker_components!($($(# $tt)* $id),*);
+
+        pub type $bundle = ($($id,)*);
+    };
+    ($($(# $tt:tt)*$id:ident<$param:ident>),*) => {
+        $(
+            $(# $tt)*
+            pub struct $id<$param>(::std::marker::PhantomData<$param>);
+
+            // implemented by hand so they don't require bounds on `$param`
+            impl<$param> ::std::clone::Clone for $id<$param> {
+                fn clone(&self) -> Self {
+                    *self
+                }
+            }
+
+            impl<$param> ::std::marker::Copy for $id<$param> {}
+
+            impl<$param> ::std::default::Default for $id<$param> {
+                fn default() -> Self {
+                    Self(::std::marker::PhantomData)
+                }
+            }
+
+            impl<$param: Send + Sync + 'static> ::bevy::ecs::component::Component for $id<$param> {
+                type Storage = ::bevy::ecs::component::TableStorage;
+            }
+
+            impl<$param> ::std::hash::Hash for $id<$param> {
+                fn hash<H: ::std::hash::Hasher>(&self, _state: &mut H) {}
+            }
+
+            impl<$param> ::std::cmp::PartialEq for $id<$param> {
+                fn eq(&self, _other: &Self) -> bool {
+                    true
+                }
+            }
+
+            impl<$param> ::std::cmp::Eq for $id<$param> {}
+
+            impl<$param> ::std::cmp::PartialOrd for $id<$param> {
+                fn partial_cmp(&self, other: &Self) -> ::std::option::Option<::std::cmp::Ordering> {
+                    ::std::option::Option::Some(::std::cmp::Ord::cmp(self, other))
+                }
+            }
+
+            impl<$param> ::std::cmp::Ord for $id<$param> {
+                fn cmp(&self, _other: &Self) -> ::std::cmp::Ordering {
+                    ::std::cmp::Ordering::Equal
+                }
+            }
+        )*
+    };
     ($($(# $tt:tt)*$id:ident),*) => {
         $(
             #[derive(Clone, Copy, Default, ::bevy::ecs::component::Component, Hash, PartialEq, Eq, PartialOrd, Ord)]
@@ -33,7 +183,7 @@ macro_rules! marker_components {
     };
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub struct SquareSprite {
     pub x: f32,
     pub y: f32,
@@ -41,6 +191,36 @@ pub struct SquareSprite {
     pub color: Color,
     pub size: f32,
     pub grid: f32,
+    /// separate horizontal and vertical grid spacing, used instead of `grid` when set
+    pub grid_xy: Option<Vec2>,
+    /// world offset added to the grid position
+    pub origin: Vec2,
+    /// rotation about the z axis, in radians
+    pub rotation: f32,
+    pub anchor: Anchor,
+    /// an optional texture, tinted by `color`
+    pub texture: Option<Handle<Image>>,
+    /// outline width and color, drawn by a child sprite from `spawn_square_sprite`.
+    /// `square_sprite` only builds the bundle, so it ignores this
+    pub border: Option<(f32, Color)>,
+}
+
+// `Anchor` doesn't implement `PartialEq`, so compare it by its vector
+impl PartialEq for SquareSprite {
+    fn eq(&self, other: &Self) -> bool {
+        self.x == other.x
+            && self.y == other.y
+            && self.z == other.z
+            && self.color == other.color
+            && self.size == other.size
+            && self.grid == other.grid
+            && self.grid_xy == other.grid_xy
+            && self.origin == other.origin
+            && self.rotation == other.rotation
+            && self.anchor.as_vec() == other.anchor.as_vec()
+            && self.texture == other.texture
+            && self.border == other.border
+    }
 }
 
 impl Default for SquareSprite {
@@ -52,186 +232,2288 @@ impl Default for SquareSprite {
             color: Color::BLACK,
             size: 100.0,
             grid: 100.0,
+            grid_xy: None,
+            origin: Vec2::ZERO,
+            rotation: 0.0,
+            anchor: Anchor::Center,
+            texture: None,
+            border: None,
+        }
+    }
+}
+
+impl SquareSprite {
+    pub fn builder() -> SquareSpriteBuilder {
+        SquareSpriteBuilder::default()
+    }
+
+    pub fn on_layer(mut self, layer: Layer) -> Self {
+        self.z = layer.z();
+        self
+    }
+
+    /// Snap `z` to the integer slot `n`, avoiding precision issues between nearby layers
+    pub fn z_slot(mut self, n: i32) -> Self {
+        self.z = n as f32;
+        self
+    }
+
+    /// Scale `size`, the grid spacing and the border width by `scale`, e.g. for a zoom level.
+    /// `origin` is a world offset and is left as-is
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.size *= scale;
+        self.grid *= scale;
+        self.grid_xy = self.grid_xy.map(|grid| grid * scale);
+        self.border = self.border.map(|(width, color)| (width * scale, color));
+        self
+    }
+
+    /// The world translation `square_sprite` gives this sprite
+    pub fn translation(&self) -> Vec3 {
+        let position = match self.grid_xy {
+            Some(grid) => Vec2::new(
+                grid_to_world(self.x, 0.0, grid.x).x,
+                grid_to_world(0.0, self.y, grid.y).y,
+            ),
+            None => grid_to_world(self.x, self.y, self.grid),
+        };
+        (self.origin + position).extend(self.z)
+    }
+}
+
+/// Conventional z values for layering sprites
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Layer {
+    Background,
+    Tiles,
+    Entities,
+    Ui,
+}
+
+impl Layer {
+    pub fn z(&self) -> f32 {
+        match self {
+            Self::Background => 0.0,
+            Self::Tiles => 10.0,
+            Self::Entities => 20.0,
+            Self::Ui => 100.0,
         }
     }
 }
 
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SquareSpriteBuilder(SquareSprite);
+
+impl SquareSpriteBuilder {
+    pub fn at(mut self, x: f32, y: f32) -> Self {
+        self.0.x = x;
+        self.0.y = y;
+        self
+    }
+
+    pub fn z(mut self, z: f32) -> Self {
+        self.0.z = z;
+        self
+    }
+
+    pub fn on_layer(mut self, layer: Layer) -> Self {
+        self.0 = self.0.on_layer(layer);
+        self
+    }
+
+    pub fn z_slot(mut self, n: i32) -> Self {
+        self.0 = self.0.z_slot(n);
+        self
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.0.color = color;
+        self
+    }
+
+    pub fn size(mut self, size: f32) -> Self {
+        self.0.size = size;
+        self
+    }
+
+    pub fn grid(mut self, grid: f32) -> Self {
+        self.0.grid = grid;
+        self
+    }
+
+    pub fn grid_xy(mut self, grid: Vec2) -> Self {
+        self.0.grid_xy = Some(grid);
+        self
+    }
+
+    pub fn origin(mut self, origin: Vec2) -> Self {
+        self.0.origin = origin;
+        self
+    }
+
+    pub fn rotation(mut self, rotation: f32) -> Self {
+        self.0.rotation = rotation;
+        self
+    }
+
+    pub fn anchor(mut self, anchor: Anchor) -> Self {
+        self.0.anchor = anchor;
+        self
+    }
+
+    pub fn texture(mut self, texture: Handle<Image>) -> Self {
+        self.0.texture = Some(texture);
+        self
+    }
+
+    pub fn border(mut self, width: f32, color: Color) -> Self {
+        self.0.border = Some((width, color));
+        self
+    }
+
+    pub fn build(self) -> SquareSprite {
+        self.0
+    }
+}
+
+/// The bundle for `sprite`, without its `border`; use `spawn_square_sprite` to draw the border too
 pub fn square_sprite(sprite: SquareSprite) -> SpriteBundle {
     SpriteBundle {
         sprite: Sprite {
             color: sprite.color,
             custom_size: Some(Vec2::new(sprite.size, sprite.size)),
+            anchor: sprite.anchor,
             ..Default::default()
         },
         transform: Transform {
-            translation: Vec3::new(sprite.x * sprite.grid, sprite.y * sprite.grid, sprite.z),
+            translation: sprite.translation(),
+            rotation: Quat::from_rotation_z(sprite.rotation),
             ..Default::default()
         },
+        texture: sprite.texture.unwrap_or_default(),
         ..Default::default()
     }
 }
 
-pub fn entity_cleanup_system<C: Component, Q: ReadOnlyWorldQuery>(
-    mut despawner: EntityDespawner,
-    query: Query<Entity, (With<C>, Q)>,
-) {
-    query.for_each(|ent| {
-        // println!("Entity Cleanup System: Despawning {ent:?}");
-        despawner.despawn_recursive(ent);
-    });
+/// The outline drawn behind `sprite`, relative to it, if it has a `border`
+pub fn square_sprite_border(sprite: &SquareSprite) -> Option<SpriteBundle> {
+    let (width, color) = sprite.border?;
+    let size = sprite.size + 2.0 * width;
+    Some(SpriteBundle {
+        sprite: Sprite {
+            color,
+            custom_size: Some(Vec2::new(size, size)),
+            ..Default::default()
+        },
+        // line up with the center of the parent sprite and sit just behind it
+        transform: Transform::from_translation(
+            (-sprite.anchor.as_vec() * sprite.size).extend(-0.01),
+        ),
+        ..Default::default()
+    })
 }
 
-pub fn resource_cleanup_system<R: Resource>(mut resource: ResourceHandle<R>) {
-    resource.remove();
+/// Spawn `square_sprite(sprite)` along with a child for its border, if it has one
+pub fn spawn_square_sprite<'w, 's, 'a>(
+    commands: &'a mut Commands<'w, 's>,
+    sprite: SquareSprite,
+) -> EntityCommands<'w, 's, 'a> {
+    let border = square_sprite_border(&sprite);
+    let mut entity = commands.spawn(square_sprite(sprite));
+    if let Some(border) = border {
+        entity.with_children(|parent| {
+            parent.spawn(border);
+        });
+    }
+    entity
 }
 
-pub struct ClosurePlugin<T: Fn(&mut App) + Send + Sync + 'static>(T);
-
-impl<T: Fn(&mut App) + Send + Sync + 'static> Plugin for ClosurePlugin<T> {
-    fn build(&self, app: &mut App) {
-        self.0(app);
+/// Convert logical grid coordinates to a world position, matching the translation of `square_sprite`.
+/// A `grid` of zero is treated as no grid, so the coordinates are used as-is
+pub fn grid_to_world(x: f32, y: f32, grid: f32) -> Vec2 {
+    if grid == 0.0 {
+        return Vec2::new(x, y);
     }
+    Vec2::new(x * grid, y * grid)
 }
 
-pub fn state_resource_plugin_given<S: States + Clone, R: Resource + Clone>(
-    state: S,
-    resource: R,
-) -> impl Plugin {
-    let insert_resource_system = move |mut handle: ResourceHandle<R>| {
-        handle.insert(resource.clone());
-    };
-    ClosurePlugin(move |app: &mut App| {
-        app.add_systems(OnEnter(state.clone()), insert_resource_system.clone())
-            .add_systems(OnExit(state.clone()), resource_cleanup_system::<R>);
-    })
+/// Convert a world position to the grid cell containing it, flooring toward negative infinity.
+/// Like `grid_to_world`, a `grid` of zero is treated as no grid
+pub fn world_to_grid(pos: Vec2, grid: f32) -> IVec2 {
+    if grid == 0.0 {
+        return pos.floor().as_ivec2();
+    }
+    (pos / grid).floor().as_ivec2()
 }
 
-pub fn state_resource_plugin_from_world<S: States + Clone, R: Resource + FromWorld>(
-    state: S,
-) -> impl Plugin {
-    let insert_resource_system = |mut resource: ResourceHandle<R>| {
-        resource.init();
-    };
-    ClosurePlugin(move |app| {
-        app.add_systems(OnEnter(state.clone()), insert_resource_system)
-            .add_systems(OnExit(state.clone()), resource_cleanup_system::<R>);
-    })
+/// Maps tile ids to colors, for building sprites from tile data
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Palette {
+    colors: HashMap<u32, Color>,
+    /// the color used for ids that aren't in the palette
+    pub fallback: Color,
 }
 
-#[derive(SystemParam)]
-pub struct EntitySpawner<'w, 's, C: Bundle + 'static>(Commands<'w, 's>, PhantomData<C>);
+impl Palette {
+    pub fn new(fallback: Color) -> Self {
+        Self {
+            colors: HashMap::default(),
+            fallback,
+        }
+    }
 
-impl<'w, 's, 'a, C: Bundle + Default> EntitySpawner<'w, 's, C> {
-    pub fn spawn_default_with(&'a mut self, bundle: impl Bundle) -> EntityCommands<'w, 's, 'a> {
-        self.0.spawn((C::default(), bundle))
+    pub fn with(mut self, id: u32, color: Color) -> Self {
+        self.insert(id, color);
+        self
     }
 
-    pub fn spawn_default(&'a mut self) -> EntityCommands<'w, 's, 'a> {
-        self.0.spawn(C::default())
+    pub fn insert(&mut self, id: u32, color: Color) {
+        self.colors.insert(id, color);
+    }
+
+    pub fn get(&self, id: u32) -> Color {
+        self.colors.get(&id).copied().unwrap_or(self.fallback)
     }
 }
 
-pub fn spawn_default_system<C: Bundle + Default>(mut spawner: EntitySpawner<C>) {
-    spawner.spawn_default();
+/// A tile filling the grid cell at `x`, `y`, colored by `id` in `palette`
+pub fn square_sprite_from_palette(
+    palette: &Palette,
+    id: u32,
+    x: f32,
+    y: f32,
+    grid: f32,
+) -> SpriteBundle {
+    square_sprite(SquareSprite {
+        x,
+        y,
+        color: palette.get(id),
+        size: grid,
+        grid,
+        ..Default::default()
+    })
 }
 
-pub struct ButtonStyle {
-    pub width: Val,
-    pub height: Val,
-    pub background_color: Color,
-    pub font_size: f32,
-    pub text_color: Color,
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RectSprite {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub color: Color,
+    pub width: f32,
+    pub height: f32,
+    pub grid: f32,
 }
 
-impl Default for ButtonStyle {
+impl Default for RectSprite {
     fn default() -> Self {
         Self {
-            width: Val::Px(150.0),
-            height: Val::Px(65.0),
-            background_color: Color::DARK_GRAY,
-            font_size: 28.0,
-            text_color: Color::WHITE,
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            color: Color::BLACK,
+            width: 100.0,
+            height: 100.0,
+            grid: 100.0,
         }
     }
 }
 
-pub fn spawn_button<B: Component + Default>(
-    parent: &mut ChildBuilder,
-    text: impl Into<String>,
-    style: ButtonStyle,
+pub fn rect_sprite(sprite: RectSprite) -> SpriteBundle {
+    SpriteBundle {
+        sprite: Sprite {
+            color: sprite.color,
+            custom_size: Some(Vec2::new(sprite.width, sprite.height)),
+            ..Default::default()
+        },
+        transform: Transform {
+            translation: grid_to_world(sprite.x, sprite.y, sprite.grid).extend(sprite.z),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+pub fn entity_cleanup_system<C: Component, Q: ReadOnlyWorldQuery>(
+    mut despawner: EntityDespawner,
+    query: Query<Entity, (With<C>, Q)>,
 ) {
-    parent
-        .spawn((
-            B::default(),
-            ButtonBundle {
-                style: Style {
-                    width: style.width,
-                    height: style.height,
-                    margin: UiRect::all(Val::Px(5.0)),
-                    border: UiRect::all(Val::Px(5.0)),
-                    // horizontally center child text
-                    justify_content: JustifyContent::Center,
-                    // vertically center child text
-                    align_items: AlignItems::Center,
-                    ..Default::default()
-                },
-                background_color: BackgroundColor(style.background_color),
-                ..Default::default()
-            },
-        ))
-        .with_children(|parent| {
-            parent.spawn(TextBundle::from_section(
-                text,
-                TextStyle {
-                    font_size: style.font_size,
-                    color: style.text_color,
-                    ..Default::default()
-                },
-            ));
-        });
+    query.for_each(|ent| {
+        // println!("Entity Cleanup System: Despawning {ent:?}");
+        despawner.despawn_recursive(ent);
+    });
 }
 
-impl<'w, 's, 'a, C: Bundle> EntitySpawner<'w, 's, C> {
-    pub fn spawn_with(&'a mut self, entity: C, bundle: impl Bundle) -> EntityCommands<'w, 's, 'a> {
-        self.0.spawn((entity, bundle))
+/// Creates a system that recursively despawns entities whose `C` matches `pred`
+pub fn despawn_where_system<C: Component>(
+    pred: impl Fn(&C) -> bool + Clone + Send + Sync + 'static,
+) -> impl Fn(EntityDespawner, Query<(Entity, &C)>) + Clone + Send + Sync + 'static {
+    move |mut despawner: EntityDespawner, query: Query<(Entity, &C)>| {
+        query.for_each(|(ent, component)| {
+            if pred(component) {
+                despawner.despawn_recursive(ent);
+            }
+        });
     }
+}
 
-    pub fn spawn(&'a mut self, entity: C) -> EntityCommands<'w, 's, 'a> {
-        self.0.spawn(entity)
-    }
+pub fn despawn_all_system<C: Component>(
+    mut despawner: EntityDespawner,
+    query: Query<Entity, With<C>>,
+) {
+    despawner.despawn_many_recursive(query.iter());
 }
 
-#[derive(SystemParam)]
-pub struct EntityDespawner<'w, 's>(Commands<'w, 's>);
+#[derive(Resource)]
+pub struct CleanupTimer<C: Component>(pub Timer, PhantomData<C>);
 
-impl<'w, 's, 'a> EntityDespawner<'w, 's> {
-    pub fn despawn(&'a mut self, entity: Entity) {
-        // println!("EntityDespawner: Despawning {entity:?}");
-        self.0.entity(entity).despawn();
+impl<C: Component> CleanupTimer<C> {
+    pub fn new(timer: Timer) -> Self {
+        Self(timer, PhantomData)
     }
+}
 
-    pub fn despawn_recursive(&'a mut self, entity: Entity) {
-        // println!("EntityDespawner: Recursively Despawning {entity:?}");
-        self.0.entity(entity).despawn_recursive();
+/// Despawns the `C` entities each time `CleanupTimer<C>` finishes; a `Once` timer only cleans up once
+pub fn timed_entity_cleanup_system<C: Component>(
+    time: Res<Time>,
+    mut timer: ResMut<CleanupTimer<C>>,
+    mut despawner: EntityDespawner,
+    query: Query<Entity, With<C>>,
+) {
+    if timer.0.tick(time.delta()).just_finished() {
+        query.for_each(|ent| {
+            despawner.despawn_recursive(ent);
+        });
     }
 }
 
-#[derive(SystemParam)]
-pub struct ResourceHandle<'w, 's, R: Resource>(Commands<'w, 's>, PhantomData<R>);
-
-impl<'w, 's, 'a, R: Resource> ResourceHandle<'w, 's, R> {
-    pub fn remove(&'a mut self) {
-        self.0.remove_resource::<R>();
+pub fn event_entity_cleanup_system<C: Component, E: Event>(
+    mut events: EventReader<E>,
+    mut despawner: EntityDespawner,
+    query: Query<Entity, With<C>>,
+) {
+    if events.is_empty() {
+        return;
     }
+    events.clear();
+    query.for_each(|ent| {
+        despawner.despawn_recursive(ent);
+    });
+}
 
-    pub fn init(&'a mut self)
-    where
-        R: FromWorld,
-    {
-        self.0.init_resource::<R>();
-    }
+/// Despawns its entity once the timer finishes; see `lifetime_system`
+#[derive(Component, Clone, Debug)]
+pub struct Lifetime(pub Timer);
 
-    pub fn insert(&'a mut self, resource: R) {
-        self.0.insert_resource(resource);
+pub fn with_lifetime(duration: Duration) -> Lifetime {
+    Lifetime(Timer::new(duration, TimerMode::Once))
+}
+
+pub fn lifetime_system(
+    time: Res<Time>,
+    mut despawner: EntityDespawner,
+    mut query: Query<(Entity, &mut Lifetime)>,
+) {
+    query.for_each_mut(|(ent, mut lifetime)| {
+        if lifetime.0.tick(time.delta()).finished() {
+            despawner.despawn_recursive(ent);
+        }
+    });
+}
+
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Health {
+    pub current: f32,
+}
+
+impl Health {
+    pub fn new(current: f32) -> Self {
+        Self { current }
+    }
+}
+
+/// Sent by `death_cleanup_system` for each entity it despawns
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Died(pub Entity);
+
+/// Recursively despawns entities that are out of health, sending `Died` if the event is registered
+pub fn death_cleanup_system(
+    mut despawner: EntityDespawner,
+    mut died: Option<ResMut<Events<Died>>>,
+    query: Query<(Entity, &Health)>,
+) {
+    query.for_each(|(ent, health)| {
+        if health.current <= 0.0 {
+            if let Some(died) = died.as_mut() {
+                died.send(Died(ent));
+            }
+            despawner.despawn_recursive(ent);
+        }
+    });
+}
+
+/// Whether gameplay is paused; `GameTime` doesn't advance while this is true
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Paused(pub bool);
+
+impl Not for Paused {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(!self.0)
+    }
+}
+
+/// Time that only advances while the game isn't `Paused`. Read this instead of `Time` in gameplay systems
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct GameTime {
+    elapsed: Duration,
+    delta: Duration,
+}
+
+impl GameTime {
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn elapsed_seconds(&self) -> f32 {
+        self.elapsed.as_secs_f32()
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+}
+
+pub fn advance_game_time_system(
+    time: Res<Time>,
+    paused: Option<Res<Paused>>,
+    mut game_time: ResMut<GameTime>,
+) {
+    let delta = match paused {
+        Some(paused) if paused.0 => Duration::ZERO,
+        _ => time.delta(),
+    };
+    game_time.delta = delta;
+    game_time.elapsed += delta;
+}
+
+/// The world-space area outside of which `offscreen_cleanup_system` despawns entities
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct CleanupBounds(pub Rect);
+
+/// Set `CleanupBounds` to the primary window's area, assuming an unscaled camera centered on the origin
+pub fn cleanup_bounds_from_window_system(
+    windows: Query<&Window, With<bevy::window::PrimaryWindow>>,
+    mut bounds: ResourceHandle<CleanupBounds>,
+) {
+    if let Ok(window) = windows.get_single() {
+        let size = Vec2::new(window.width(), window.height());
+        bounds.insert(CleanupBounds(Rect::from_center_size(Vec2::ZERO, size)));
+    }
+}
+
+/// Despawns `C` entities whose translation has left `CleanupBounds`
+pub fn offscreen_cleanup_system<C: Component>(
+    bounds: Option<Res<CleanupBounds>>,
+    mut despawner: EntityDespawner,
+    query: Query<(Entity, &Transform), With<C>>,
+) {
+    let Some(bounds) = bounds else {
+        return;
+    };
+    query.for_each(|(ent, transform)| {
+        if !bounds.0.contains(transform.translation.truncate()) {
+            despawner.despawn_recursive(ent);
+        }
+    });
+}
+
+/// Fades a `Sprite` to transparent over the timer's duration, then despawns it. UI nodes aren't supported
+#[derive(Component, Clone, Debug)]
+pub struct FadeOut {
+    pub timer: Timer,
+    start_alpha: Option<f32>,
+}
+
+impl FadeOut {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            timer: Timer::new(duration, TimerMode::Once),
+            start_alpha: None,
+        }
+    }
+}
+
+pub fn fade_out_system(
+    time: Res<Time>,
+    mut despawner: EntityDespawner,
+    mut query: Query<(Entity, &mut FadeOut, &mut Sprite)>,
+) {
+    query.for_each_mut(|(ent, mut fade, mut sprite)| {
+        let start_alpha = *fade.start_alpha.get_or_insert(sprite.color.a());
+        if fade.timer.tick(time.delta()).finished() {
+            despawner.despawn_recursive(ent);
+            return;
+        }
+        sprite.color.set_a(start_alpha * fade.timer.percent_left());
+    });
+}
+
+pub trait ShouldCleanup {
+    fn should_cleanup(&self) -> bool;
+}
+
+pub fn conditional_entity_cleanup_system<C: Component, R: Resource + ShouldCleanup>(
+    flag: Res<R>,
+    mut despawner: EntityDespawner,
+    query: Query<Entity, With<C>>,
+) {
+    if flag.should_cleanup() {
+        despawner.despawn_many_recursive(query.iter());
+    }
+}
+
+pub fn resource_cleanup_system<R: Resource>(mut resource: ResourceHandle<R>) {
+    resource.remove();
+}
+
+/// A system that inserts a clone of `value` each time it runs
+pub fn set_resource_system<R: Resource + Clone>(
+    value: R,
+) -> impl Fn(ResourceHandle<R>) + Clone + Send + Sync + 'static {
+    move |mut handle: ResourceHandle<R>| {
+        handle.insert(value.clone());
+    }
+}
+
+pub fn resource_reset_system<R: Resource + FromWorld>(mut resource: ResourceHandle<R>) {
+    resource.remove();
+    resource.init();
+}
+
+/// Run condition that passes while `R` exists, e.g. one managed by a `state_resource_plugin_*`
+pub fn resource_exists_condition<R: Resource>() -> impl FnMut(Option<Res<R>>) -> bool + Clone {
+    |resource: Option<Res<R>>| resource.is_some()
+}
+
+/// Run condition that passes while `R` does not exist
+pub fn resource_absent<R: Resource>() -> impl FnMut(Option<Res<R>>) -> bool + Clone {
+    |resource: Option<Res<R>>| resource.is_none()
+}
+
+pub struct ClosurePlugin<T: Fn(&mut App) + Send + Sync + 'static> {
+    closure: T,
+    name: Option<&'static str>,
+    unique: bool,
+}
+
+impl<T: Fn(&mut App) + Send + Sync + 'static> ClosurePlugin<T> {
+    pub fn new(closure: T) -> Self {
+        Self {
+            closure,
+            name: None,
+            unique: true,
+        }
+    }
+
+    pub fn named(name: &'static str, closure: T) -> Self {
+        Self {
+            closure,
+            name: Some(name),
+            unique: true,
+        }
+    }
+
+    /// Allow this plugin to be added to an app more than once
+    pub fn repeatable(mut self) -> Self {
+        self.unique = false;
+        self
+    }
+
+    /// Combine this with another plugin that builds after it
+    pub fn then<P: Plugin>(self, next: P) -> CombinedClosurePlugin<Self, P> {
+        CombinedClosurePlugin(self, next)
+    }
+}
+
+impl<T: Fn(&mut App) + Send + Sync + 'static> Plugin for ClosurePlugin<T> {
+    fn build(&self, app: &mut App) {
+        (self.closure)(app);
+    }
+
+    fn name(&self) -> &str {
+        self.name.unwrap_or_else(|| std::any::type_name::<Self>())
+    }
+
+    fn is_unique(&self) -> bool {
+        self.unique
+    }
+}
+
+pub fn closure_plugin<T: Fn(&mut App) + Send + Sync + 'static>(closure: T) -> ClosurePlugin<T> {
+    ClosurePlugin::new(closure)
+}
+
+/// Adds a system, usually an inline closure, to the `Startup` schedule so it runs once
+pub struct StartupClosurePlugin<S: IntoSystemConfigs<M> + Clone + Send + Sync + 'static, M> {
+    system: S,
+    marker: PhantomData<fn() -> M>,
+}
+
+impl<S: IntoSystemConfigs<M> + Clone + Send + Sync + 'static, M> StartupClosurePlugin<S, M> {
+    pub fn new(system: S) -> Self {
+        Self {
+            system,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<S: IntoSystemConfigs<M> + Clone + Send + Sync + 'static, M: 'static> Plugin
+    for StartupClosurePlugin<S, M>
+{
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, self.system.clone());
+    }
+}
+
+pub fn startup_closure_plugin<S: IntoSystemConfigs<M> + Clone + Send + Sync + 'static, M>(
+    system: S,
+) -> StartupClosurePlugin<S, M> {
+    StartupClosurePlugin::new(system)
+}
+
+/// Two plugins built in sequence, created by `ClosurePlugin::then`
+pub struct CombinedClosurePlugin<A: Plugin, B: Plugin>(A, B);
+
+impl<A: Plugin, B: Plugin> CombinedClosurePlugin<A, B> {
+    pub fn then<P: Plugin>(self, next: P) -> CombinedClosurePlugin<Self, P> {
+        CombinedClosurePlugin(self, next)
+    }
+}
+
+impl<A: Plugin, B: Plugin> Plugin for CombinedClosurePlugin<A, B> {
+    fn build(&self, app: &mut App) {
+        self.0.build(app);
+        self.1.build(app);
+    }
+}
+
+#[derive(Resource, Default)]
+struct AppliedOnceClosurePlugins(HashSet<&'static str>);
+
+/// A `ClosurePlugin` that only builds the first time its key is added to an app
+pub struct OnceClosurePlugin<T: Fn(&mut App) + Send + Sync + 'static> {
+    key: &'static str,
+    closure: T,
+}
+
+impl<T: Fn(&mut App) + Send + Sync + 'static> OnceClosurePlugin<T> {
+    pub fn new(key: &'static str, closure: T) -> Self {
+        Self { key, closure }
+    }
+}
+
+impl<T: Fn(&mut App) + Send + Sync + 'static> Plugin for OnceClosurePlugin<T> {
+    fn build(&self, app: &mut App) {
+        let mut applied = app
+            .world
+            .get_resource_or_insert_with(AppliedOnceClosurePlugins::default);
+        if applied.0.insert(self.key) {
+            (self.closure)(app);
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.key
+    }
+
+    fn is_unique(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct StateScoped<S: States>(pub S);
+
+/// Despawns every `StateScoped<S>` entity whose state isn't the current one
+pub fn state_scoped_cleanup_system<S: States>(
+    state: Res<State<S>>,
+    mut despawner: EntityDespawner,
+    query: Query<(Entity, &StateScoped<S>)>,
+) {
+    query.for_each(|(ent, scope)| {
+        if &scope.0 != state.get() {
+            despawner.despawn_recursive(ent);
+        }
+    });
+}
+
+/// Despawns `StateScoped<S>` entities when their state is exited
+#[derive(Default)]
+pub struct StateScopePlugin<S: States>(PhantomData<S>);
+
+impl<S: States> Plugin for StateScopePlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            StateTransition,
+            state_scoped_cleanup_system::<S>
+                .after(apply_state_transition::<S>)
+                .run_if(state_changed::<S>()),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SingletonPolicy {
+    /// Panic in debug builds and log an error in release builds
+    #[default]
+    Panic,
+    Warn,
+    /// Despawn every entity except the one that existed first
+    DespawnExtras,
+}
+
+/// Enforces that at most one entity with `C` exists
+pub struct SingletonPlugin<C: Component> {
+    policy: SingletonPolicy,
+    marker: PhantomData<C>,
+}
+
+impl<C: Component> SingletonPlugin<C> {
+    pub fn new(policy: SingletonPolicy) -> Self {
+        Self {
+            policy,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<C: Component> Default for SingletonPlugin<C> {
+    fn default() -> Self {
+        Self::new(SingletonPolicy::default())
+    }
+}
+
+impl<C: Component> Plugin for SingletonPlugin<C> {
+    fn build(&self, app: &mut App) {
+        let policy = self.policy;
+        app.add_systems(
+            Update,
+            move |despawner: EntityDespawner,
+                  query: Query<Entity, With<C>>,
+                  kept: Local<Option<Entity>>| {
+                singleton_system(policy, despawner, query, kept);
+            },
+        );
+    }
+}
+
+fn singleton_system<C: Component>(
+    policy: SingletonPolicy,
+    mut despawner: EntityDespawner,
+    query: Query<Entity, With<C>>,
+    mut kept: Local<Option<Entity>>,
+) {
+    // query order follows archetypes, not age, so remember which entity was here first
+    if !kept.is_some_and(|kept| query.contains(kept)) {
+        *kept = query.iter().next();
+    }
+    let count = query.iter().len();
+    if count <= 1 {
+        return;
+    }
+    let name = std::any::type_name::<C>();
+    match policy {
+        SingletonPolicy::Panic if cfg!(debug_assertions) => {
+            panic!("found {count} entities with singleton component {name}")
+        }
+        SingletonPolicy::Panic => error!("found {count} entities with singleton component {name}"),
+        SingletonPolicy::Warn => warn!("found {count} entities with singleton component {name}"),
+        SingletonPolicy::DespawnExtras => {
+            despawner.despawn_many_recursive(query.iter().filter(|ent| Some(*ent) != *kept))
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ForbidPolicy {
+    /// Panic in debug builds and log an error in release builds
+    #[default]
+    Panic,
+    Warn,
+    /// Recursively despawn the offending entities
+    Despawn,
+}
+
+/// Creates a system that applies `policy` to any entity with `C` matching `Q`,
+/// e.g. `forbid_component_system::<Player, With<Enemy>>` for a combination that shouldn't exist
+pub fn forbid_component_system<C: Component, Q: ReadOnlyWorldQuery + 'static>(
+    policy: ForbidPolicy,
+) -> impl Fn(EntityDespawner, Query<Entity, (With<C>, Q)>) + Clone + Send + Sync + 'static {
+    move |mut despawner: EntityDespawner, query: Query<Entity, (With<C>, Q)>| {
+        query.for_each(|ent| {
+            let name = std::any::type_name::<C>();
+            match policy {
+                ForbidPolicy::Panic if cfg!(debug_assertions) => {
+                    panic!("{ent:?} has forbidden component {name}")
+                }
+                ForbidPolicy::Panic => error!("{ent:?} has forbidden component {name}"),
+                ForbidPolicy::Warn => warn!("{ent:?} has forbidden component {name}"),
+                ForbidPolicy::Despawn => despawner.despawn_recursive(ent),
+            }
+        });
+    }
+}
+
+pub fn state_resource_plugin_given<S: States + Clone, R: Resource + Clone>(
+    state: S,
+    resource: R,
+) -> impl Plugin {
+    let insert_resource_system = set_resource_system(resource);
+    ClosurePlugin::new(move |app: &mut App| {
+        app.add_systems(OnEnter(state.clone()), insert_resource_system.clone())
+            .add_systems(OnExit(state.clone()), resource_cleanup_system::<R>);
+    })
+}
+
+/// Like `state_resource_plugin_given`, but the resource lives while in any of `states`.
+/// Moving between two of the states keeps the existing resource; entering the set from
+/// outside it inserts a fresh clone of `resource`
+pub fn state_resource_plugin_given_many<S: States + Clone, R: Resource + Clone>(
+    states: impl IntoIterator<Item = S>,
+    resource: R,
+) -> impl Plugin {
+    let states: Vec<S> = states.into_iter().collect();
+    let insert_resource_system = move |mut handle: ResourceHandle<R>| {
+        handle.insert_if_missing(resource.clone());
+    };
+    ClosurePlugin::new(move |app: &mut App| {
+        for state in &states {
+            let states = states.clone();
+            // `State<S>` already holds the entered state when `OnExit` runs
+            let cleanup_system = move |current: Res<State<S>>, handle: ResourceHandle<R>| {
+                if !states.contains(current.get()) {
+                    resource_cleanup_system(handle);
+                }
+            };
+            app.add_systems(OnEnter(state.clone()), insert_resource_system.clone())
+                .add_systems(OnExit(state.clone()), cleanup_system);
+        }
+    })
+}
+
+/// Inserts `resource` the first time `state` is entered and never removes it,
+/// so its value persists across exits and re-entries
+pub fn persistent_state_resource_plugin<S: States + Clone, R: Resource + Clone>(
+    state: S,
+    resource: R,
+) -> impl Plugin {
+    let insert_resource_system = move |mut handle: ResourceHandle<R>| {
+        handle.insert_if_missing(resource.clone());
+    };
+    ClosurePlugin::new(move |app: &mut App| {
+        app.add_systems(OnEnter(state.clone()), insert_resource_system.clone());
+    })
+}
+
+pub fn state_resource_plugin_from_world<S: States + Clone, R: Resource + FromWorld>(
+    state: S,
+) -> impl Plugin {
+    let insert_resource_system = |mut resource: ResourceHandle<R>| {
+        resource.init();
+    };
+    ClosurePlugin::new(move |app| {
+        app.add_systems(OnEnter(state.clone()), insert_resource_system)
+            .add_systems(OnExit(state.clone()), resource_cleanup_system::<R>);
+    })
+}
+
+/// Like `state_resource_plugin_from_world`, but builds the resource with `f` on enter
+pub fn state_resource_plugin_with<S: States + Clone, R: Resource>(
+    state: S,
+    f: impl Fn(&mut World) -> R + Send + Sync + 'static,
+) -> impl Plugin {
+    let f = Arc::new(f);
+    ClosurePlugin::new(move |app: &mut App| {
+        let f = f.clone();
+        let insert_resource_system = move |world: &mut World| {
+            let resource = f(world);
+            world.insert_resource(resource);
+        };
+        app.add_systems(OnEnter(state.clone()), insert_resource_system)
+            .add_systems(OnExit(state.clone()), resource_cleanup_system::<R>);
+    })
+}
+
+/// Queues transitions of `S`. Use this instead of `ResMut<NextState<S>>` so state changes are easy to find
+#[derive(SystemParam)]
+pub struct StateSetter<'w, S: States>(ResMut<'w, NextState<S>>);
+
+impl<'w, S: States> StateSetter<'w, S> {
+    pub fn set(&mut self, state: S) {
+        self.0.set(state);
+    }
+}
+
+/// Switches between states `a` and `b` when `key` is pressed in either of them, e.g. escape to pause
+pub fn toggle_state_plugin<S: States + Clone>(a: S, b: S, key: KeyCode) -> impl Plugin {
+    ClosurePlugin::new(move |app: &mut App| {
+        let (a, b) = (a.clone(), b.clone());
+        app.add_systems(
+            Update,
+            move |keys: Res<Input<KeyCode>>, state: Res<State<S>>, mut setter: StateSetter<S>| {
+                if !keys.just_pressed(key) {
+                    return;
+                }
+                if *state.get() == a {
+                    setter.set(b.clone());
+                } else if *state.get() == b {
+                    setter.set(a.clone());
+                }
+            },
+        );
+    })
+}
+
+/// Inserts `R::from(seed)` on startup, e.g. a deterministic RNG wrapper for reproducible runs
+pub fn seeded_rng_plugin<R: Resource + From<u64>>(seed: u64) -> impl Plugin {
+    ClosurePlugin::new(move |app: &mut App| {
+        app.add_systems(Startup, move |mut rng: ResourceHandle<R>| {
+            rng.insert(R::from(seed));
+        });
+    })
+}
+
+pub fn state_entity_cleanup_plugin<S: States + Clone, C: Component>(state: S) -> impl Plugin {
+    ClosurePlugin::new(move |app: &mut App| {
+        app.add_systems(OnExit(state.clone()), entity_cleanup_system::<C, ()>);
+    })
+}
+
+/// Shows `C` entities while in `visible_in` and hides them otherwise, without despawning them
+pub fn state_visibility_plugin<S: States + Clone, C: Component>(visible_in: S) -> impl Plugin {
+    ClosurePlugin::new(move |app: &mut App| {
+        app.add_systems(
+            OnEnter(visible_in.clone()),
+            set_visibility_system::<C>(Visibility::Visible),
+        )
+        .add_systems(
+            OnExit(visible_in.clone()),
+            set_visibility_system::<C>(Visibility::Hidden),
+        );
+    })
+}
+
+fn set_visibility_system<C: Component>(
+    visibility: Visibility,
+) -> impl Fn(Query<&mut Visibility, With<C>>) + Clone + Send + Sync + 'static {
+    move |mut query: Query<&mut Visibility, With<C>>| {
+        query.for_each_mut(|mut current| {
+            current.set_if_neq(visibility);
+        });
+    }
+}
+
+#[derive(SystemParam)]
+pub struct EntitySpawner<'w, 's, C: Bundle + 'static>(Commands<'w, 's>, PhantomData<C>);
+
+impl<'w, 's, 'a, C: Bundle + Default> EntitySpawner<'w, 's, C> {
+    pub fn spawn_default_with(&'a mut self, bundle: impl Bundle) -> EntityCommands<'w, 's, 'a> {
+        self.0.spawn((C::default(), bundle))
+    }
+
+    pub fn spawn_default(&'a mut self) -> EntityCommands<'w, 's, 'a> {
+        self.0.spawn(C::default())
+    }
+
+    pub fn spawn_default_at(&'a mut self, sprite: SquareSprite) -> EntityCommands<'w, 's, 'a> {
+        let mut entity = spawn_square_sprite(&mut self.0, sprite);
+        entity.insert(C::default());
+        entity
+    }
+
+    pub fn spawn_default_scoped<S: States>(&'a mut self, state: S) -> EntityCommands<'w, 's, 'a> {
+        self.0.spawn((C::default(), StateScoped(state)))
+    }
+
+    pub fn spawn_default_under(&'a mut self, parent: Entity) -> EntityCommands<'w, 's, 'a> {
+        let mut commands = self.0.spawn(C::default());
+        commands.set_parent(parent);
+        commands
+    }
+
+    pub fn spawn_default_with_children(
+        &'a mut self,
+        f: impl FnOnce(&mut ChildBuilder),
+    ) -> EntityCommands<'w, 's, 'a> {
+        let mut commands = self.0.spawn(C::default());
+        commands.with_children(f);
+        commands
+    }
+
+    pub fn spawn_batch_default(&mut self, count: usize) {
+        self.0.spawn_batch((0..count).map(|_| C::default()));
+    }
+
+    /// Spawn `count` defaults one by one, returning their ids
+    pub fn spawn_default_collect(&mut self, count: usize) -> Vec<Entity> {
+        (0..count).map(|_| self.spawn_default().id()).collect()
+    }
+
+    /// Spawn `count` defaults as children of `parent`, returning their ids
+    pub fn spawn_default_batch_under(&mut self, parent: Entity, count: usize) -> Vec<Entity> {
+        (0..count)
+            .map(|_| self.spawn_default_under(parent).id())
+            .collect()
+    }
+
+    pub fn spawn_batch_with<B: Bundle>(&mut self, bundles: impl IntoIterator<Item = B>) {
+        let batch: Vec<_> = bundles.into_iter().map(|b| (C::default(), b)).collect();
+        self.0.spawn_batch(batch);
+    }
+}
+
+pub fn spawn_default_system<C: Bundle + Default>(mut spawner: EntitySpawner<C>) {
+    spawner.spawn_default();
+}
+
+#[derive(Resource)]
+pub struct LastSpawned<C: Bundle>(pub Entity, PhantomData<C>);
+
+pub fn spawn_default_system_tracked<C: Bundle + Default>(
+    mut spawner: EntitySpawner<C>,
+    mut last_spawned: ResourceHandle<LastSpawned<C>>,
+) {
+    let entity = spawner.spawn_default().id();
+    last_spawned.insert(LastSpawned(entity, PhantomData));
+}
+
+/// Rebuilds the `C` entities whenever `R` changes, ignoring the change from `R` being added
+pub fn respawn_on_resource_change_system<C: Component + Default, R: Resource>(
+    resource: Res<R>,
+    mut despawner: EntityDespawner,
+    mut spawner: EntitySpawner<C>,
+    query: Query<Entity, With<C>>,
+) {
+    if !resource.is_changed() || resource.is_added() {
+        return;
+    }
+    despawner.despawn_many_recursive(query.iter());
+    spawner.spawn_default();
+}
+
+/// Counts the entities with `C` that match the filter `Q`
+#[derive(SystemParam)]
+pub struct Count<'w, 's, C: Component, Q: ReadOnlyWorldQuery + 'static = ()>(
+    Query<'w, 's, (), (With<C>, Q)>,
+);
+
+impl<'w, 's, C: Component, Q: ReadOnlyWorldQuery + 'static> Count<'w, 's, C, Q> {
+    pub fn get(&self) -> usize {
+        self.0.iter().count()
+    }
+}
+
+#[derive(Resource)]
+pub struct EntityCount<C: Component>(pub usize, PhantomData<C>);
+
+impl<C: Component> Default for EntityCount<C> {
+    fn default() -> Self {
+        Self(0, PhantomData)
+    }
+}
+
+pub fn entity_count_system<C: Component>(entities: Count<C>, mut count: ResMut<EntityCount<C>>) {
+    let current = entities.get();
+    if count.0 != current {
+        count.0 = current;
+    }
+}
+
+/// The `EntityCount` kept up to date by `CountTrackerPlugin`
+pub type TrackedCount<C> = EntityCount<C>;
+
+/// Updates `TrackedCount<C>` in `PreUpdate`, so systems in `Update` can read it without their own query
+pub struct CountTrackerPlugin<C: Component>(PhantomData<C>);
+
+impl<C: Component> Default for CountTrackerPlugin<C> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<C: Component> Plugin for CountTrackerPlugin<C> {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TrackedCount<C>>()
+            .add_systems(PreUpdate, entity_count_system::<C>);
+    }
+}
+
+/// Entities collected by `collect_entities_system`, sorted by `Entity` so the order is stable between frames.
+/// This isn't spawn order, since entity ids are recycled
+#[derive(Resource)]
+pub struct EntityList<M>(pub Vec<Entity>, PhantomData<M>);
+
+impl<M> Default for EntityList<M> {
+    fn default() -> Self {
+        Self(Vec::new(), PhantomData)
+    }
+}
+
+/// Stores every entity with `C` in `EntityList<M>`
+pub fn collect_entities_system<C: Component, M: Send + Sync + 'static>(
+    query: Query<Entity, With<C>>,
+    mut list: ResMut<EntityList<M>>,
+) {
+    let mut entities: Vec<Entity> = query.iter().collect();
+    entities.sort();
+    if list.0 != entities {
+        list.0 = entities;
+    }
+}
+
+/// Updates `EntityList<M>` with the entities with `C` in `PreUpdate`
+pub struct CollectPlugin<C: Component, M: Send + Sync + 'static = C>(PhantomData<(C, M)>);
+
+impl<C: Component, M: Send + Sync + 'static> Default for CollectPlugin<C, M> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<C: Component, M: Send + Sync + 'static> Plugin for CollectPlugin<C, M> {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EntityList<M>>()
+            .add_systems(PreUpdate, collect_entities_system::<C, M>);
+    }
+}
+
+#[derive(Resource)]
+struct EntityCountLogTimer<C: Component>(Timer, PhantomData<C>);
+
+fn entity_count_logger_system<C: Component>(
+    time: Res<Time>,
+    mut timer: ResMut<EntityCountLogTimer<C>>,
+    count: Res<EntityCount<C>>,
+) {
+    if timer.0.tick(time.delta()).just_finished() {
+        info!("{} entities with {}", count.0, std::any::type_name::<C>());
+    }
+}
+
+/// Keeps `EntityCount<C>` up to date and logs it every `interval`
+pub struct EntityCountLoggerPlugin<C: Component> {
+    interval: Duration,
+    marker: PhantomData<C>,
+}
+
+impl<C: Component> EntityCountLoggerPlugin<C> {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<C: Component> Default for EntityCountLoggerPlugin<C> {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(1))
+    }
+}
+
+impl<C: Component> Plugin for EntityCountLoggerPlugin<C> {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EntityCount<C>>()
+            .insert_resource(EntityCountLogTimer::<C>(
+                Timer::new(self.interval, TimerMode::Repeating),
+                PhantomData,
+            ))
+            .add_systems(
+                Update,
+                (entity_count_system::<C>, entity_count_logger_system::<C>).chain(),
+            );
+    }
+}
+
+/// How many times `ResourceChangeLoggerPlugin<R>` has seen `R` change
+#[derive(Resource)]
+pub struct ResourceChangeCount<R: Resource>(pub usize, PhantomData<R>);
+
+impl<R: Resource> Default for ResourceChangeCount<R> {
+    fn default() -> Self {
+        Self(0, PhantomData)
+    }
+}
+
+fn resource_change_logger_system<R: Resource + std::fmt::Debug>(
+    resource: Res<R>,
+    mut count: ResMut<ResourceChangeCount<R>>,
+) {
+    count.0 += 1;
+    info!("{} changed: {:?}", std::any::type_name::<R>(), *resource);
+}
+
+/// Logs `R` each time it is added or changed
+pub struct ResourceChangeLoggerPlugin<R: Resource + std::fmt::Debug>(PhantomData<R>);
+
+impl<R: Resource + std::fmt::Debug> Default for ResourceChangeLoggerPlugin<R> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<R: Resource + std::fmt::Debug> Plugin for ResourceChangeLoggerPlugin<R> {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ResourceChangeCount<R>>().add_systems(
+            Update,
+            resource_change_logger_system::<R>.run_if(resource_exists_and_changed::<R>()),
+        );
+    }
+}
+
+#[derive(Resource)]
+pub struct SpawnLimit<C: Component> {
+    pub max: usize,
+    marker: PhantomData<C>,
+}
+
+impl<C: Component> SpawnLimit<C> {
+    pub fn new(max: usize) -> Self {
+        Self {
+            max,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'w, 's, 'a, C: Component + Default> EntitySpawner<'w, 's, C> {
+    /// Spawn `C::default()` unless `count` has already reached `limit`.
+    /// `count` is incremented on success so repeated calls in one frame respect the limit
+    pub fn spawn_default_limited(
+        &'a mut self,
+        limit: &SpawnLimit<C>,
+        count: &mut EntityCount<C>,
+    ) -> Option<EntityCommands<'w, 's, 'a>> {
+        if count.0 >= limit.max {
+            return None;
+        }
+        count.0 += 1;
+        Some(self.spawn_default())
+    }
+}
+
+#[derive(Resource)]
+pub struct SpawnCooldown<C: Component> {
+    pub timer: Timer,
+    marker: PhantomData<C>,
+}
+
+impl<C: Component> SpawnCooldown<C> {
+    /// Create a cooldown that is ready immediately
+    pub fn new(duration: Duration) -> Self {
+        let mut timer = Timer::new(duration, TimerMode::Once);
+        timer.tick(duration);
+        Self {
+            timer,
+            marker: PhantomData,
+        }
+    }
+}
+
+pub fn spawn_cooldown_system<C: Component>(
+    time: Res<Time>,
+    mut cooldown: ResMut<SpawnCooldown<C>>,
+) {
+    cooldown.timer.tick(time.delta());
+}
+
+impl<'w, 's, 'a, C: Component + Default> EntitySpawner<'w, 's, C> {
+    /// Spawn `C::default()` if `cooldown` has finished, restarting it on success
+    pub fn spawn_default_on_cooldown(
+        &'a mut self,
+        cooldown: &mut SpawnCooldown<C>,
+    ) -> Option<EntityCommands<'w, 's, 'a>> {
+        if !cooldown.timer.finished() {
+            return None;
+        }
+        cooldown.timer.reset();
+        Some(self.spawn_default())
+    }
+}
+
+/// Stable spawn-order index assigned by `EntitySpawner::spawn_default_indexed`
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IndexedMarker(pub usize);
+
+/// The next index handed out to entities with `C`
+#[derive(Resource)]
+pub struct SpawnCounter<C: Component>(pub usize, PhantomData<C>);
+
+impl<C: Component> Default for SpawnCounter<C> {
+    fn default() -> Self {
+        Self(0, PhantomData)
+    }
+}
+
+impl<'w, 's, 'a, C: Component + Default> EntitySpawner<'w, 's, C> {
+    /// Spawn `C::default()` tagged with the next index from `SpawnCounter<C>`.
+    /// The index is assigned when commands are applied, so it follows spawn order
+    pub fn spawn_default_indexed(&'a mut self) -> EntityCommands<'w, 's, 'a> {
+        let entity = self.0.spawn(C::default()).id();
+        self.0.add(move |world: &mut World| {
+            let mut counter = world.get_resource_or_insert_with(SpawnCounter::<C>::default);
+            let index = counter.0;
+            counter.0 += 1;
+            if let Some(mut entity) = world.get_entity_mut(entity) {
+                entity.insert(IndexedMarker(index));
+            }
+        });
+        self.0.entity(entity)
+    }
+}
+
+fn shift_brightness(color: Color, amount: f32) -> Color {
+    let shift = |channel: f32| (channel + amount).clamp(0.0, 1.0);
+    let [r, g, b, a] = color.as_rgba_f32();
+    Color::rgba(shift(r), shift(g), shift(b), a)
+}
+
+#[derive(Clone, Debug)]
+pub struct ButtonStyle {
+    pub width: Val,
+    pub height: Val,
+    pub background_color: Color,
+    /// the background while hovered, or a lighter `background_color` if `None`
+    pub hovered_color: Option<Color>,
+    /// the background while pressed, or a darker `background_color` if `None`
+    pub pressed_color: Option<Color>,
+    pub disabled_color: Color,
+    pub border_color: Color,
+    /// the font for the button text, or bevy's default font if `None`
+    pub font: Option<Handle<Font>>,
+    pub font_size: f32,
+    pub text_color: Color,
+    /// space between the button's border and its text
+    pub padding: UiRect,
+}
+
+impl Default for ButtonStyle {
+    fn default() -> Self {
+        Self {
+            width: Val::Px(150.0),
+            height: Val::Px(65.0),
+            background_color: Color::DARK_GRAY,
+            hovered_color: None,
+            pressed_color: None,
+            disabled_color: Color::rgba(0.25, 0.25, 0.25, 0.5),
+            // `ButtonBundle`'s default, so the border is invisible unless a color is set
+            border_color: Color::NONE,
+            font: None,
+            font_size: 28.0,
+            text_color: Color::WHITE,
+            padding: UiRect::all(Val::Px(4.0)),
+        }
+    }
+}
+
+impl ButtonStyle {
+    pub fn hovered(&self) -> Color {
+        self.hovered_color
+            .unwrap_or_else(|| shift_brightness(self.background_color, 0.1))
+    }
+
+    pub fn pressed(&self) -> Color {
+        self.pressed_color
+            .unwrap_or_else(|| shift_brightness(self.background_color, -0.1))
+    }
+
+    pub fn primary() -> Self {
+        Self {
+            background_color: Color::rgb(0.15, 0.35, 0.75),
+            hovered_color: Some(Color::rgb(0.25, 0.45, 0.85)),
+            pressed_color: Some(Color::rgb(0.1, 0.25, 0.55)),
+            border_color: Color::rgb(0.6, 0.75, 1.0),
+            ..Default::default()
+        }
+    }
+
+    pub fn danger() -> Self {
+        Self {
+            background_color: Color::rgb(0.7, 0.15, 0.15),
+            hovered_color: Some(Color::rgb(0.85, 0.25, 0.25)),
+            pressed_color: Some(Color::rgb(0.5, 0.1, 0.1)),
+            border_color: Color::rgb(1.0, 0.6, 0.6),
+            ..Default::default()
+        }
+    }
+
+    pub fn small() -> Self {
+        Self {
+            width: Val::Px(100.0),
+            height: Val::Px(40.0),
+            font_size: 18.0,
+            padding: UiRect::all(Val::Px(2.0)),
+            ..Default::default()
+        }
+    }
+}
+
+pub fn spawn_button<B: Component + Default>(
+    parent: &mut ChildBuilder,
+    text: impl Into<String>,
+    style: ButtonStyle,
+) -> Entity {
+    spawn_button_with::<B>(parent, text, style, ())
+}
+
+pub fn spawn_button_with<B: Component + Default>(
+    parent: &mut ChildBuilder,
+    text: impl Into<String>,
+    style: ButtonStyle,
+    extra: impl Bundle,
+) -> Entity {
+    parent
+        .spawn((
+            B::default(),
+            extra,
+            ButtonColors::from_style(&style),
+            ButtonBundle {
+                style: Style {
+                    width: style.width,
+                    height: style.height,
+                    margin: UiRect::all(Val::Px(5.0)),
+                    border: UiRect::all(Val::Px(5.0)),
+                    padding: style.padding,
+                    // horizontally center child text
+                    justify_content: JustifyContent::Center,
+                    // vertically center child text
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                background_color: BackgroundColor(style.background_color),
+                border_color: BorderColor(style.border_color),
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                text,
+                TextStyle {
+                    font: style.font.unwrap_or_default(),
+                    font_size: style.font_size,
+                    color: style.text_color,
+                },
+            ));
+        })
+        .id()
+}
+
+/// Spawn a node laying out one `B` button per label in a grid with `cols` columns, returning the grid node
+pub fn spawn_button_grid<B: Component + Default>(
+    parent: &mut ChildBuilder,
+    labels: &[&str],
+    cols: usize,
+    style: ButtonStyle,
+) -> Entity {
+    spawn_button_grid_with::<B, _>(parent, labels, cols, style, |_| ())
+}
+
+/// Like `spawn_button_grid`, also inserting `extra(index)` on each button
+pub fn spawn_button_grid_with<B: Component + Default, E: Bundle>(
+    parent: &mut ChildBuilder,
+    labels: &[&str],
+    cols: usize,
+    style: ButtonStyle,
+    mut extra: impl FnMut(usize) -> E,
+) -> Entity {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                display: Display::Grid,
+                grid_template_columns: RepeatedGridTrack::auto(cols.max(1) as u16),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with_children(|grid| {
+            for (index, label) in labels.iter().enumerate() {
+                spawn_button_with::<B>(grid, *label, style.clone(), extra(index));
+            }
+        })
+        .id()
+}
+
+#[derive(Event)]
+pub struct ButtonClicked<B: Component>(pub Entity, PhantomData<B>);
+
+impl<B: Component> ButtonClicked<B> {
+    pub fn new(entity: Entity) -> Self {
+        Self(entity, PhantomData)
+    }
+}
+
+/// Filter for `B` buttons that aren't disabled and whose `Interaction` changed
+type ChangedEnabledButton<B> = (Changed<Interaction>, With<B>, Without<ButtonDisabled>);
+
+/// Sends `ButtonClicked<B>` once each time an enabled `B` button becomes pressed.
+/// Buttons with a `ButtonDebounce` send at most one click per cooldown
+pub fn button_click_event_system<B: Component>(
+    mut query: Query<(Entity, &Interaction, Option<&mut ButtonDebounce>), ChangedEnabledButton<B>>,
+    mut events: EventWriter<ButtonClicked<B>>,
+) {
+    query.for_each_mut(|(entity, interaction, debounce)| {
+        if *interaction != Interaction::Pressed {
+            return;
+        }
+        if let Some(mut debounce) = debounce {
+            if !debounce.timer.finished() {
+                return;
+            }
+            debounce.timer.reset();
+        }
+        events.send(ButtonClicked::new(entity));
+    });
+}
+
+/// Limits a button to one `ButtonClicked` per cooldown; tick it with `button_debounce_system`
+#[derive(Component, Clone, Debug)]
+pub struct ButtonDebounce {
+    pub timer: Timer,
+}
+
+impl ButtonDebounce {
+    /// Create a debounce that allows a click immediately
+    pub fn new(cooldown: Duration) -> Self {
+        let mut timer = Timer::new(cooldown, TimerMode::Once);
+        timer.tick(cooldown);
+        Self { timer }
+    }
+}
+
+pub fn button_debounce_system(time: Res<Time>, mut query: Query<&mut ButtonDebounce>) {
+    query.for_each_mut(|mut debounce| {
+        debounce.timer.tick(time.delta());
+    });
+}
+
+/// Creates a system that moves to the state `target` when an enabled `B` button is pressed
+pub fn state_transition_button_system<B: Component, S: States + Clone>(
+    target: S,
+) -> impl Fn(Query<&Interaction, ChangedEnabledButton<B>>, ResMut<NextState<S>>)
+       + Clone
+       + Send
+       + Sync
+       + 'static {
+    move |query, mut next_state| {
+        if query
+            .iter()
+            .any(|interaction| *interaction == Interaction::Pressed)
+        {
+            next_state.set(target.clone());
+        }
+    }
+}
+
+type SpawnChild = Box<dyn FnOnce(&mut ChildBuilder)>;
+
+/// Lays out buttons as children of a single root node
+pub struct ButtonMenu {
+    layout: Style,
+    buttons: Vec<SpawnChild>,
+}
+
+impl Default for ButtonMenu {
+    fn default() -> Self {
+        Self {
+            layout: Style {
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            buttons: Vec::new(),
+        }
+    }
+}
+
+impl ButtonMenu {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn layout(mut self, layout: Style) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    pub fn button<B: Component + Default>(
+        mut self,
+        text: impl Into<String>,
+        style: ButtonStyle,
+    ) -> Self {
+        let text = text.into();
+        self.buttons.push(Box::new(move |parent| {
+            spawn_button::<B>(parent, text, style);
+        }));
+        self
+    }
+
+    pub fn spawn(self, commands: &mut Commands) -> Entity {
+        commands
+            .spawn(NodeBundle {
+                style: self.layout,
+                ..Default::default()
+            })
+            .with_children(|parent| {
+                for button in self.buttons {
+                    button(parent);
+                }
+            })
+            .id()
+    }
+}
+
+/// Spawn a full-screen root node to hold a menu; chain `.with_children` to add buttons
+pub fn spawn_ui_root<'w, 's, 'a>(
+    commands: &'a mut Commands<'w, 's>,
+    justify: JustifyContent,
+    align: AlignItems,
+) -> EntityCommands<'w, 's, 'a> {
+    commands.spawn(NodeBundle {
+        style: Style {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            justify_content: justify,
+            align_items: align,
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+}
+
+/// The index of the keyboard-focused `B` button, in the order the buttons appear among their parent's children
+#[derive(Resource)]
+pub struct MenuFocus<B: Component>(pub usize, PhantomData<B>);
+
+impl<B: Component> Default for MenuFocus<B> {
+    fn default() -> Self {
+        Self(0, PhantomData)
+    }
+}
+
+type MenuButton<'a> = (
+    Entity,
+    Option<&'a Parent>,
+    &'a mut Interaction,
+    &'a ButtonColors,
+    &'a mut BackgroundColor,
+);
+
+/// Moves `MenuFocus<B>` with the arrow keys and tab, wrapping at either end, and presses the focused button on enter.
+/// The focused button is shown with its hovered color. Buttons are meant to share a parent, like those
+/// from `ButtonMenu`; buttons under different parents don't have a meaningful order between them
+pub fn menu_navigation_system<B: Component>(
+    keys: Res<Input<KeyCode>>,
+    mut focus: ResMut<MenuFocus<B>>,
+    mut buttons: Query<MenuButton, (With<B>, Without<ButtonDisabled>)>,
+    children: Query<&Children>,
+) {
+    // `Entity` ordering doesn't follow spawn order once ids are recycled, so use the order of the children
+    let mut order: Vec<(usize, Entity)> = buttons
+        .iter()
+        .map(|(entity, parent, ..)| {
+            let position = parent
+                .and_then(|parent| children.get(parent.get()).ok())
+                .and_then(|children| children.iter().position(|child| *child == entity))
+                .unwrap_or(usize::MAX);
+            (position, entity)
+        })
+        .collect();
+    if order.is_empty() {
+        return;
+    }
+    order.sort();
+    let entities: Vec<Entity> = order.into_iter().map(|(_, entity)| entity).collect();
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let forward = keys.any_just_pressed([KeyCode::Down, KeyCode::Right])
+        || (keys.just_pressed(KeyCode::Tab) && !shift);
+    let backward = keys.any_just_pressed([KeyCode::Up, KeyCode::Left])
+        || (keys.just_pressed(KeyCode::Tab) && shift);
+    let count = entities.len();
+    let mut index = focus.0 % count;
+    if forward {
+        index = (index + 1) % count;
+    }
+    if backward {
+        index = (index + count - 1) % count;
+    }
+    if focus.0 != index {
+        focus.0 = index;
+    }
+    let focused = entities[index];
+    let moved = focus.is_changed();
+    let enter = keys.any_just_pressed([KeyCode::Return, KeyCode::NumpadEnter]);
+    buttons.for_each_mut(|(entity, _, mut interaction, colors, mut background)| {
+        if moved {
+            let color = if entity == focused {
+                colors.hovered
+            } else {
+                colors.get(*interaction, false)
+            };
+            background.0 = color;
+        }
+        if enter && entity == focused {
+            // assign rather than `set_if_neq` so a held button still registers as changed
+            *interaction = Interaction::Pressed;
+        }
+    });
+}
+
+/// The background colors of a button spawned by `spawn_button` for each `Interaction`
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct ButtonColors {
+    pub normal: Color,
+    pub hovered: Color,
+    pub pressed: Color,
+    pub disabled: Color,
+}
+
+impl ButtonColors {
+    pub fn from_style(style: &ButtonStyle) -> Self {
+        ButtonColors {
+            normal: style.background_color,
+            hovered: style.hovered(),
+            pressed: style.pressed(),
+            disabled: style.disabled_color,
+        }
+    }
+
+    pub fn get(&self, interaction: Interaction, disabled: bool) -> Color {
+        match interaction {
+            _ if disabled => self.disabled,
+            Interaction::Pressed => self.pressed,
+            Interaction::Hovered => self.hovered,
+            Interaction::None => self.normal,
+        }
+    }
+}
+
+/// Marks a button as disabled; use `set_button_disabled` to keep its color in sync
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct ButtonDisabled;
+
+pub fn set_button_disabled(commands: &mut Commands, entity: Entity, disabled: bool) {
+    commands.add(move |world: &mut World| {
+        let Some(mut button) = world.get_entity_mut(entity) else {
+            return;
+        };
+        if disabled {
+            button.insert(ButtonDisabled);
+        } else {
+            button.remove::<ButtonDisabled>();
+        }
+        let (Some(&interaction), Some(&colors)) =
+            (button.get::<Interaction>(), button.get::<ButtonColors>())
+        else {
+            return;
+        };
+        if let Some(mut background) = button.get_mut::<BackgroundColor>() {
+            background.0 = colors.get(interaction, disabled);
+        }
+    });
+}
+
+/// Apply `style` to a button spawned by `spawn_button`, including its text, e.g. when switching themes
+pub fn restyle_button(commands: &mut Commands, entity: Entity, style: ButtonStyle) {
+    commands.add(move |world: &mut World| {
+        let Some(mut button) = world.get_entity_mut(entity) else {
+            return;
+        };
+        let colors = ButtonColors::from_style(&style);
+        let interaction = button.get::<Interaction>().copied().unwrap_or_default();
+        let disabled = button.contains::<ButtonDisabled>();
+        button.insert((
+            colors,
+            BackgroundColor(colors.get(interaction, disabled)),
+            BorderColor(style.border_color),
+        ));
+        if let Some(mut node_style) = button.get_mut::<Style>() {
+            node_style.width = style.width;
+            node_style.height = style.height;
+            node_style.padding = style.padding;
+        }
+        let children = button
+            .get::<Children>()
+            .map(|children| children.to_vec())
+            .unwrap_or_default();
+        for child in children {
+            let Some(mut text) = world.get_mut::<Text>(child) else {
+                continue;
+            };
+            for section in &mut text.sections {
+                section.style = TextStyle {
+                    font: style.font.clone().unwrap_or_default(),
+                    font_size: style.font_size,
+                    color: style.text_color,
+                };
+            }
+        }
+    });
+}
+
+pub fn button_interaction_system<B: Component>(
+    mut query: Query<(&Interaction, &ButtonColors, &mut BackgroundColor), ChangedEnabledButton<B>>,
+) {
+    query.for_each_mut(|(interaction, colors, mut background)| {
+        background.0 = colors.get(*interaction, false);
+    });
+}
+
+impl<'w, 's, 'a, C: Bundle> EntitySpawner<'w, 's, C> {
+    pub fn spawn_with(&'a mut self, entity: C, bundle: impl Bundle) -> EntityCommands<'w, 's, 'a> {
+        self.0.spawn((entity, bundle))
+    }
+
+    pub fn spawn(&'a mut self, entity: C) -> EntityCommands<'w, 's, 'a> {
+        self.0.spawn(entity)
+    }
+}
+
+#[derive(SystemParam)]
+pub struct EntityDespawner<'w, 's>(Commands<'w, 's>);
+
+impl<'w, 's, 'a> EntityDespawner<'w, 's> {
+    pub fn despawn(&'a mut self, entity: Entity) {
+        // println!("EntityDespawner: Despawning {entity:?}");
+        self.0.entity(entity).despawn();
+    }
+
+    pub fn despawn_recursive(&'a mut self, entity: Entity) {
+        // println!("EntityDespawner: Recursively Despawning {entity:?}");
+        self.0.entity(entity).despawn_recursive();
+    }
+
+    pub fn despawn_many(&'a mut self, entities: impl IntoIterator<Item = Entity>) {
+        for entity in entities {
+            self.0.entity(entity).despawn();
+        }
+    }
+
+    pub fn despawn_many_recursive(&'a mut self, entities: impl IntoIterator<Item = Entity>) {
+        for entity in entities {
+            self.0.entity(entity).despawn_recursive();
+        }
+    }
+
+    /// Despawn `entity` if it still exists when the command is applied, ignoring stale ids
+    pub fn despawn_if_exists(&'a mut self, entity: Entity) {
+        self.0.add(move |world: &mut World| {
+            if let Some(entity) = world.get_entity_mut(entity) {
+                entity.despawn();
+            }
+        });
+    }
+
+    /// Despawn `entity`, then run `f`. The entity is gone by the time `f` runs, so capture anything needed from it first
+    pub fn despawn_then(&'a mut self, entity: Entity, f: impl FnOnce(&mut World) + Send + 'static) {
+        self.0.entity(entity).despawn();
+        self.0.add(f);
+    }
+
+    /// Recursively despawn all of `parent`'s children, keeping `parent` itself
+    pub fn despawn_children(&'a mut self, parent: Entity) {
+        self.0.entity(parent).despawn_descendants();
+    }
+
+    /// Recursively despawn every entity matched by `query`
+    pub fn despawn_matching<C: Component, Q: ReadOnlyWorldQuery>(
+        &'a mut self,
+        query: &Query<Entity, (With<C>, Q)>,
+    ) {
+        self.despawn_many_recursive(query.iter());
+    }
+
+    /// Recursively despawn `entity` if it still exists when the command is applied
+    pub fn despawn_if_exists_recursive(&'a mut self, entity: Entity) {
+        self.0.add(move |world: &mut World| {
+            if world.get_entity(entity).is_some() {
+                despawn_with_children_recursive(world, entity);
+            }
+        });
+    }
+
+    pub fn despawn_with(&'a mut self, entity: Entity, recursive: bool) {
+        if recursive {
+            self.despawn_recursive(entity);
+        } else {
+            self.despawn(entity);
+        }
+    }
+}
+
+/// Entities queued by `EntityDespawner::despawn_deferred`, despawned in `DespawnSet`
+#[derive(Resource, Default)]
+struct DespawnQueue(Vec<Entity>);
+
+/// Runs in `Last`, after every other schedule in the frame, and despawns queued entities
+#[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DespawnSet;
+
+impl<'w, 's, 'a> EntityDespawner<'w, 's> {
+    /// Recursively despawn `entity` in `DespawnSet` instead of at the next command flush,
+    /// so it outlives anything else done this frame. Needs `DespawnSetPlugin`
+    pub fn despawn_deferred(&'a mut self, entity: Entity) {
+        self.0.add(move |world: &mut World| {
+            world
+                .get_resource_or_insert_with(DespawnQueue::default)
+                .0
+                .push(entity);
+        });
+    }
+}
+
+fn despawn_queue_system(mut queue: ResMut<DespawnQueue>, mut despawner: EntityDespawner) {
+    for entity in queue.0.drain(..) {
+        despawner.despawn_if_exists_recursive(entity);
+    }
+}
+
+pub struct DespawnSetPlugin;
+
+impl Plugin for DespawnSetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DespawnQueue>()
+            .add_systems(Last, despawn_queue_system.in_set(DespawnSet));
+    }
+}
+
+/// Marks an entity to be despawned when `ResetGame` is sent
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Resettable;
+
+/// Starts a new game; handled by `ResetPlugin`
+#[derive(Event, Clone, Copy, Debug, Default)]
+pub struct ResetGame;
+
+/// On `ResetGame`, recursively despawns every `Resettable` entity and re-initializes the registered resources
+#[derive(Default)]
+pub struct ResetPlugin {
+    resources: Vec<fn(&mut World)>,
+}
+
+impl ResetPlugin {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reset `R` to `R::from_world` on `ResetGame`
+    pub fn resource<R: Resource + FromWorld>(mut self) -> Self {
+        self.resources.push(|world: &mut World| {
+            let resource = R::from_world(world);
+            world.insert_resource(resource);
+        });
+        self
+    }
+}
+
+impl Plugin for ResetPlugin {
+    fn build(&self, app: &mut App) {
+        let resources = self.resources.clone();
+        app.add_event::<ResetGame>().add_systems(
+            Update,
+            move |world: &mut World, mut reader: Local<ManualEventReader<ResetGame>>| {
+                let events = world.resource::<Events<ResetGame>>();
+                if reader.read(events).count() == 0 {
+                    return;
+                }
+                let resettable: Vec<Entity> = world
+                    .query_filtered::<Entity, With<Resettable>>()
+                    .iter(world)
+                    .collect();
+                for entity in resettable {
+                    if world.get_entity(entity).is_some() {
+                        despawn_with_children_recursive(world, entity);
+                    }
+                }
+                for reset in &resources {
+                    reset(world);
+                }
+            },
+        );
+    }
+}
+
+/// Recursively despawns `entity` and spawns a fresh `C::default()` in its place
+pub fn reset_entity<C: Bundle + Default>(
+    despawner: &mut EntityDespawner,
+    spawner: &mut EntitySpawner<C>,
+    entity: Entity,
+) {
+    despawner.despawn_recursive(entity);
+    spawner.spawn_default();
+}
+
+/// Spawn a `cols` by `rows` block of `C::default()` sprites, one grid cell apart, starting at `template`'s position.
+/// Each cell is spawned like `EntitySpawner::spawn_default_at`, so a `border` on the template is kept
+pub fn spawn_grid<C: Bundle + Default>(
+    spawner: &mut EntitySpawner<C>,
+    cols: u32,
+    rows: u32,
+    template: SquareSprite,
+) {
+    for row in 0..rows {
+        for col in 0..cols {
+            spawner.spawn_default_at(SquareSprite {
+                x: template.x + col as f32,
+                y: template.y + row as f32,
+                ..template.clone()
+            });
+        }
+    }
+}
+
+/// Replace `From` on `entity` with `To::default()`, e.g. moving from `Idle` to `Walking`
+pub fn swap_marker<From: Component, To: Component + Default>(
+    commands: &mut Commands,
+    entity: Entity,
+) {
+    commands
+        .entity(entity)
+        .remove::<From>()
+        .insert(To::default());
+}
+
+/// Queues changes to the components of existing entities
+#[derive(SystemParam)]
+pub struct EntityEditor<'w, 's>(Commands<'w, 's>);
+
+impl<'w, 's, 'a> EntityEditor<'w, 's> {
+    pub fn swap_marker<From: Component, To: Component + Default>(&'a mut self, entity: Entity) {
+        swap_marker::<From, To>(&mut self.0, entity);
+    }
+}
+
+/// Reads `R` and queues changes to it. Since this holds `Res<R>`, it can't share a system with `ResMut<R>`
+#[derive(SystemParam)]
+pub struct ResourceHandle<'w, 's, R: Resource>(Commands<'w, 's>, Option<Res<'w, R>>);
+
+impl<'w, 's, 'a, R: Resource> ResourceHandle<'w, 's, R> {
+    /// The value of the resource when the system started; queued changes aren't visible until they're applied
+    pub fn get(&self) -> Option<&R> {
+        self.1.as_deref()
+    }
+
+    pub fn remove(&'a mut self) {
+        self.0.remove_resource::<R>();
+    }
+
+    pub fn init(&'a mut self)
+    where
+        R: FromWorld,
+    {
+        self.0.init_resource::<R>();
+    }
+
+    pub fn insert(&'a mut self, resource: R) {
+        self.0.insert_resource(resource);
+    }
+
+    pub fn insert_if_missing(&'a mut self, resource: R) {
+        self.0.add(move |world: &mut World| {
+            if !world.contains_resource::<R>() {
+                world.insert_resource(resource);
+            }
+        });
+    }
+
+    /// Insert the value produced by `f` if the resource is missing once commands are applied
+    pub fn get_or_insert_with(&'a mut self, f: impl FnOnce() -> R + Send + 'static) {
+        self.0.add(move |world: &mut World| {
+            if !world.contains_resource::<R>() {
+                world.insert_resource(f());
+            }
+        });
+    }
+
+    pub fn modify(&'a mut self, f: impl FnOnce(&mut R) + Send + 'static) {
+        self.0.add(move |world: &mut World| {
+            if let Some(mut resource) = world.get_resource_mut::<R>() {
+                f(&mut resource);
+            }
+        });
+    }
+
+    /// Apply `f` only if `pred` holds for the resource's value when commands are applied
+    pub fn modify_if(
+        &'a mut self,
+        pred: impl Fn(&R) -> bool + Send + 'static,
+        f: impl FnOnce(&mut R) + Send + 'static,
+    ) {
+        self.0.add(move |world: &mut World| {
+            if let Some(mut resource) = world.get_resource_mut::<R>() {
+                if pred(&resource) {
+                    f(&mut resource);
+                }
+            }
+        });
+    }
+
+    /// Replace the resource with `f` of its value. It's updated in place, so it reads as changed rather than added
+    pub fn toggle_with(&'a mut self, f: impl FnOnce(R) -> R + Send + 'static)
+    where
+        R: Clone,
+    {
+        self.0.add(move |world: &mut World| {
+            if let Some(mut resource) = world.get_resource_mut::<R>() {
+                *resource = f(resource.clone());
+            }
+        });
+    }
+
+    pub fn toggle(&'a mut self)
+    where
+        R: Clone + Not<Output = R>,
+    {
+        self.toggle_with(R::not);
+    }
+}
+
+/// Resources added by `ResourceHandle::insert_transient`, with the number of updates each has left
+#[derive(Resource, Default)]
+struct TransientResources(Vec<(TypeId, u32, RemoveResource)>);
+
+type RemoveResource = fn(&mut World);
+
+impl<'w, 's, 'a, R: Resource> ResourceHandle<'w, 's, R> {
+    /// Insert `resource` and remove it after `frames` more updates. Needs `TransientResourcePlugin`
+    pub fn insert_transient(&'a mut self, resource: R, frames: u32) {
+        self.0.add(move |world: &mut World| {
+            world.insert_resource(resource);
+            let mut transients = world.get_resource_or_insert_with(TransientResources::default);
+            transients.0.retain(|(id, ..)| *id != TypeId::of::<R>());
+            transients
+                .0
+                .push((TypeId::of::<R>(), frames, |world: &mut World| {
+                    world.remove_resource::<R>();
+                }));
+        });
+    }
+}
+
+fn transient_resource_system(world: &mut World) {
+    let Some(mut transients) = world.get_resource_mut::<TransientResources>() else {
+        return;
+    };
+    let mut expired = Vec::new();
+    transients.0.retain_mut(|(_, frames, remove)| {
+        if *frames == 0 {
+            expired.push(*remove);
+            return false;
+        }
+        *frames -= 1;
+        true
+    });
+    for remove in expired {
+        remove(world);
+    }
+}
+
+/// Removes resources added by `ResourceHandle::insert_transient` once they expire
+pub struct TransientResourcePlugin;
+
+impl Plugin for TransientResourcePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TransientResources>()
+            .add_systems(First, transient_resource_system);
+    }
+}
+
+/// A resource holding `T` along with how many times it has been replaced
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+pub struct Versioned<T: Send + Sync + 'static> {
+    value: T,
+    version: u64,
+}
+
+impl<T: Send + Sync + 'static> Versioned<T> {
+    pub fn new(value: T) -> Self {
+        Self { value, version: 0 }
+    }
+
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Replace the value, bumping the version
+    pub fn set(&mut self, value: T) {
+        self.value = value;
+        self.version += 1;
+    }
+}
+
+impl<'w, 's, 'a, T: Send + Sync + 'static> ResourceHandle<'w, 's, Versioned<T>> {
+    /// Replace the value once commands are applied, inserting it at version 0 if the resource is missing
+    pub fn set_versioned(&'a mut self, value: T) {
+        self.0.add(
+            move |world: &mut World| match world.get_resource_mut::<Versioned<T>>() {
+                Some(mut versioned) => versioned.set(value),
+                None => world.insert_resource(Versioned::new(value)),
+            },
+        );
+    }
+}
+
+/// A saved copy of `R`, written by `ResourceHandle::snapshot`
+#[derive(Resource, Clone, Debug)]
+pub struct ResourceSnapshot<R: Resource + Clone>(pub R);
+
+impl<'w, 's, 'a, R: Resource + Clone> ResourceHandle<'w, 's, R> {
+    /// Save a copy of the resource, as it is once commands are applied, into `ResourceSnapshot<R>`
+    pub fn snapshot(&'a mut self) {
+        self.0.add(|world: &mut World| {
+            if let Some(resource) = world.get_resource::<R>().cloned() {
+                world.insert_resource(ResourceSnapshot(resource));
+            }
+        });
+    }
+
+    /// Overwrite the resource with the last snapshot, if there is one. The snapshot is kept
+    pub fn restore(&'a mut self) {
+        self.0.add(|world: &mut World| {
+            if let Some(snapshot) = world.get_resource::<ResourceSnapshot<R>>() {
+                let resource = snapshot.0.clone();
+                world.insert_resource(resource);
+            }
+        });
+    }
+}
+
+/// Read-only access to a resource. Prefer this over `ResMut` when a system doesn't need to mutate `R`;
+/// writes should go through `ResourceHandle`
+#[derive(SystemParam)]
+pub struct ReadOnlyRes<'w, R: Resource>(Res<'w, R>);
+
+impl<'w, R: Resource> ReadOnlyRes<'w, R> {
+    pub fn get(&self) -> &R {
+        &self.0
+    }
+}
+
+impl<'w, R: Resource> Deref for ReadOnlyRes<'w, R> {
+    type Target = R;
+
+    fn deref(&self) -> &R {
+        &self.0
     }
 }
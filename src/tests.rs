@@ -1,3 +1,2373 @@
-use super::marker_components;
+use std::time::Duration;
+
+use bevy::{
+    ecs::{query::ReadOnlyWorldQuery, system::RunSystemOnce},
+    prelude::*,
+    sprite::Anchor,
+};
+
+use super::{marker_components, prelude::*};
 
 marker_components! {MyMarker}
+
+marker_components! {reflect: ReflectedA, ReflectedB}
+
+marker_components! {bundle AllTags; TagA, TagB, TagC}
+
+marker_components! {PlayButton, QuitButton}
+
+marker_components! {Tagged<T>}
+
+marker_components! {derive(Debug); DebugA, DebugB}
+
+marker_components! {enum TerrainKind; Grass, Water, Rock}
+
+fn count<F: ReadOnlyWorldQuery>(world: &mut World) -> usize {
+    world.query_filtered::<(), F>().iter(world).count()
+}
+
+fn advance_time(app: &mut App, millis: u64) {
+    app.world
+        .resource_mut::<Time>()
+        .advance_by(Duration::from_millis(millis));
+    app.update();
+}
+
+#[test]
+fn square_sprite_builder_defaults() {
+    assert_eq!(SquareSprite::builder().build(), SquareSprite::default());
+}
+
+#[test]
+fn square_sprite_builder_setters() {
+    assert_eq!(
+        SquareSprite::builder().at(3.0, 4.0).build(),
+        SquareSprite {
+            x: 3.0,
+            y: 4.0,
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        SquareSprite::builder().z(2.0).build(),
+        SquareSprite {
+            z: 2.0,
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        SquareSprite::builder().color(Color::RED).build(),
+        SquareSprite {
+            color: Color::RED,
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        SquareSprite::builder().size(16.0).build(),
+        SquareSprite {
+            size: 16.0,
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        SquareSprite::builder().grid(32.0).build(),
+        SquareSprite {
+            grid: 32.0,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn square_sprite_builder_spawns() {
+    let mut world = World::new();
+    let entity = world
+        .spawn((
+            MyMarker,
+            square_sprite(SquareSprite::builder().at(3.0, 4.0).grid(10.0).build()),
+        ))
+        .id();
+    assert_eq!(
+        world.get::<Transform>(entity).unwrap().translation,
+        Vec3::new(30.0, 40.0, 0.0)
+    );
+}
+
+#[test]
+fn rect_sprite_size_and_translation() {
+    let bundle = rect_sprite(RectSprite {
+        x: 2.0,
+        y: -1.0,
+        z: 3.0,
+        width: 50.0,
+        height: 100.0,
+        grid: 50.0,
+        ..Default::default()
+    });
+    assert_eq!(bundle.sprite.custom_size, Some(Vec2::new(50.0, 100.0)));
+    assert_eq!(bundle.transform.translation, Vec3::new(100.0, -50.0, 3.0));
+}
+
+#[test]
+fn square_sprite_rotation() {
+    assert_eq!(
+        square_sprite(SquareSprite::default()).transform.rotation,
+        Quat::IDENTITY
+    );
+    let bundle = square_sprite(SquareSprite {
+        x: 1.0,
+        rotation: std::f32::consts::FRAC_PI_2,
+        ..Default::default()
+    });
+    assert!(bundle
+        .transform
+        .rotation
+        .abs_diff_eq(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2), 1e-6));
+    assert!((bundle.transform.rotation * Vec3::X).abs_diff_eq(Vec3::Y, 1e-6));
+    assert_eq!(bundle.transform.translation, Vec3::new(100.0, 0.0, 0.0));
+}
+
+#[test]
+fn square_sprite_anchor() {
+    assert!(matches!(SquareSprite::default().anchor, Anchor::Center));
+    let bundle = square_sprite(SquareSprite::builder().anchor(Anchor::BottomLeft).build());
+    assert!(matches!(bundle.sprite.anchor, Anchor::BottomLeft));
+    assert_ne!(
+        SquareSprite::builder().anchor(Anchor::BottomLeft).build(),
+        SquareSprite::default()
+    );
+}
+
+#[test]
+fn grid_world_conversion() {
+    assert_eq!(grid_to_world(-2.0, 3.0, 50.0), Vec2::new(-100.0, 150.0));
+    assert_eq!(
+        world_to_grid(Vec2::new(-1.0, -50.0), 50.0),
+        IVec2::new(-1, -1)
+    );
+    assert_eq!(
+        world_to_grid(Vec2::new(-51.0, 49.0), 50.0),
+        IVec2::new(-2, 0)
+    );
+    for (x, y) in [(0, 0), (3, -4), (-7, 2), (10, 10)] {
+        let world = grid_to_world(x as f32, y as f32, 64.0);
+        assert_eq!(world_to_grid(world, 64.0), IVec2::new(x, y));
+        let sprite = square_sprite(SquareSprite {
+            x: x as f32,
+            y: y as f32,
+            grid: 64.0,
+            ..Default::default()
+        });
+        assert_eq!(sprite.transform.translation.truncate(), world);
+    }
+}
+
+#[test]
+fn spawner_batch() {
+    let mut world = World::new();
+    world.run_system_once(|mut spawner: EntitySpawner<MyMarker>| {
+        spawner.spawn_batch_default(100);
+    });
+    assert_eq!(count::<With<MyMarker>>(&mut world), 100);
+
+    let mut world = World::new();
+    world.run_system_once(|mut spawner: EntitySpawner<MyMarker>| {
+        spawner.spawn_batch_with((0..100).map(|i| Name::new(i.to_string())));
+    });
+    assert_eq!(count::<(With<MyMarker>, With<Name>)>(&mut world), 100);
+}
+
+#[test]
+fn spawn_default_system_tracked_records_entity() {
+    let mut world = World::new();
+    world.run_system_once(spawn_default_system_tracked::<MyMarker>);
+    let entity = world.resource::<LastSpawned<MyMarker>>().0;
+    assert!(world.get::<MyMarker>(entity).is_some());
+}
+
+#[test]
+fn spawner_with_children() {
+    let mut world = World::new();
+    let parent = world.run_system_once(|mut spawner: EntitySpawner<MyMarker>| {
+        spawner
+            .spawn_default_with_children(|parent| {
+                parent.spawn_empty();
+                parent.spawn_empty();
+            })
+            .id()
+    });
+    assert!(world.get::<MyMarker>(parent).is_some());
+    assert_eq!(world.get::<Children>(parent).unwrap().len(), 2);
+}
+
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+struct Score(u32);
+
+#[test]
+fn resource_handle_modify() {
+    let mut world = World::new();
+    world.insert_resource(Score(1));
+    world.run_system_once(|mut score: ResourceHandle<Score>| {
+        score.modify(|score| score.0 += 2);
+    });
+    assert_eq!(world.resource::<Score>(), &Score(3));
+}
+
+#[test]
+fn resource_handle_modify_absent() {
+    let mut world = World::new();
+    world.run_system_once(|mut score: ResourceHandle<Score>| {
+        score.modify(|score| score.0 += 2);
+    });
+    assert!(!world.contains_resource::<Score>());
+}
+
+#[test]
+fn resource_handle_insert_if_missing() {
+    let mut world = World::new();
+    world.run_system_once(|mut score: ResourceHandle<Score>| {
+        score.insert_if_missing(Score(1));
+        score.insert_if_missing(Score(2));
+    });
+    assert_eq!(world.resource::<Score>(), &Score(1));
+}
+
+#[test]
+fn resource_handle_toggle() {
+    let mut world = World::new();
+    world.insert_resource(Paused(false));
+    world.run_system_once(|mut paused: ResourceHandle<Paused>| paused.toggle());
+    assert_eq!(world.resource::<Paused>(), &Paused(true));
+    world.run_system_once(|mut paused: ResourceHandle<Paused>| paused.toggle());
+    assert_eq!(world.resource::<Paused>(), &Paused(false));
+
+    world.insert_resource(Score(1));
+    world.run_system_once(|mut score: ResourceHandle<Score>| {
+        score.toggle_with(|score| Score(score.0 * 10));
+    });
+    assert_eq!(world.resource::<Score>(), &Score(10));
+}
+
+#[test]
+fn timed_entity_cleanup() {
+    let mut app = App::new();
+    app.init_resource::<Time>()
+        .insert_resource(CleanupTimer::<MyMarker>::new(Timer::from_seconds(
+            1.0,
+            TimerMode::Once,
+        )))
+        .add_systems(Update, timed_entity_cleanup_system::<MyMarker>);
+    app.world.spawn(MyMarker);
+    app.world.spawn(MyMarker);
+
+    app.world
+        .resource_mut::<Time>()
+        .advance_by(Duration::from_millis(600));
+    app.update();
+    assert_eq!(count::<With<MyMarker>>(&mut app.world), 2);
+
+    app.world
+        .resource_mut::<Time>()
+        .advance_by(Duration::from_millis(600));
+    app.update();
+    assert_eq!(count::<With<MyMarker>>(&mut app.world), 0);
+
+    // entities spawned after a `Once` timer has fired are left alone
+    app.world.spawn(MyMarker);
+    advance_time(&mut app, 600);
+    advance_time(&mut app, 600);
+    assert_eq!(count::<With<MyMarker>>(&mut app.world), 1);
+}
+
+#[derive(Event)]
+struct RoundOver;
+
+fn event_cleanup_app() -> App {
+    let mut app = App::new();
+    app.add_event::<RoundOver>()
+        .add_systems(Update, event_entity_cleanup_system::<MyMarker, RoundOver>);
+    app.world.spawn(MyMarker);
+    app.world.spawn(MyMarker);
+    app
+}
+
+#[test]
+fn event_entity_cleanup() {
+    let mut app = event_cleanup_app();
+    app.world.send_event(RoundOver);
+    app.world.send_event(RoundOver);
+    app.update();
+    assert_eq!(count::<With<MyMarker>>(&mut app.world), 0);
+
+    // the events were drained, so later entities survive
+    app.world.spawn(MyMarker);
+    app.update();
+    assert_eq!(count::<With<MyMarker>>(&mut app.world), 1);
+}
+
+#[test]
+fn event_entity_cleanup_without_event() {
+    let mut app = event_cleanup_app();
+    app.update();
+    assert_eq!(count::<With<MyMarker>>(&mut app.world), 2);
+}
+
+#[derive(States, Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+enum GameState {
+    #[default]
+    Menu,
+    Playing,
+    Paused,
+}
+
+fn set_state(app: &mut App, state: GameState) {
+    app.world.resource_mut::<NextState<GameState>>().set(state);
+    app.update();
+}
+
+#[test]
+fn state_scoped_cleanup() {
+    let mut app = App::new();
+    app.add_state::<GameState>()
+        .add_plugins(StateScopePlugin::<GameState>::default());
+    let menu = app
+        .world
+        .run_system_once(|mut spawner: EntitySpawner<MyMarker>| {
+            spawner.spawn_default_scoped(GameState::Menu).id()
+        });
+    app.update();
+    assert!(app.world.get_entity(menu).is_some());
+
+    app.world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Playing);
+    app.update();
+    assert!(app.world.get_entity(menu).is_none());
+    let playing = app
+        .world
+        .run_system_once(|mut spawner: EntitySpawner<MyMarker>| {
+            spawner.spawn_default_scoped(GameState::Playing).id()
+        });
+    app.update();
+    assert!(app.world.get_entity(playing).is_some());
+
+    app.world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Menu);
+    app.update();
+    assert!(app.world.get_entity(playing).is_none());
+
+    // a transition on the very first frame still cleans up the exited state
+    let mut app = App::new();
+    app.add_state::<GameState>()
+        .add_plugins(StateScopePlugin::<GameState>::default())
+        .add_systems(
+            Startup,
+            |mut spawner: EntitySpawner<MyMarker>, mut next: ResMut<NextState<GameState>>| {
+                spawner.spawn_default_scoped(GameState::Menu);
+                next.set(GameState::Playing);
+            },
+        );
+    app.update();
+    app.update();
+    assert_eq!(
+        app.world
+            .query::<&StateScoped<GameState>>()
+            .iter(&app.world)
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn closure_plugin_name() {
+    let plugin = ClosurePlugin::named("my_plugin", |_: &mut App| {});
+    assert_eq!(plugin.name(), "my_plugin");
+    let plugin = ClosurePlugin::new(|_: &mut App| {});
+    assert!(plugin.name().starts_with("bevy_restrict::ClosurePlugin<"));
+}
+
+#[test]
+fn closure_plugin_repeatable() {
+    fn score_plugin() -> impl Plugin {
+        ClosurePlugin::new(|app: &mut App| {
+            let score = app.world.get_resource::<Score>().map_or(0, |s| s.0);
+            app.insert_resource(Score(score + 1));
+        })
+        .repeatable()
+    }
+    let mut app = App::new();
+    app.add_plugins(score_plugin()).add_plugins(score_plugin());
+    assert_eq!(app.world.resource::<Score>(), &Score(2));
+}
+
+#[test]
+fn marker_components_reflect() {
+    let mut app = App::new();
+    register_markers(&mut app);
+    let registry = app.world.resource::<AppTypeRegistry>().read();
+    for id in [
+        std::any::TypeId::of::<ReflectedA>(),
+        std::any::TypeId::of::<ReflectedB>(),
+    ] {
+        assert!(registry.get_type_data::<ReflectComponent>(id).is_some());
+    }
+}
+
+#[test]
+fn marker_components_bundle() {
+    let mut world = World::new();
+    world.spawn(AllTags::default());
+    world.spawn(TagA);
+    assert_eq!(count::<(With<TagA>, With<TagB>, With<TagC>)>(&mut world), 1);
+}
+
+#[test]
+fn despawn_all() {
+    let mut world = World::new();
+    world.spawn(MyMarker);
+    world.spawn(MyMarker);
+    world.spawn(TagA);
+    world.run_system_once(despawn_all_system::<MyMarker>);
+    assert_eq!(count::<With<MyMarker>>(&mut world), 0);
+    assert_eq!(count::<With<TagA>>(&mut world), 1);
+}
+
+fn spawn_family(world: &mut World) -> (Entity, Entity) {
+    let mut child = Entity::PLACEHOLDER;
+    let parent = world
+        .spawn(MyMarker)
+        .with_children(|parent| {
+            child = parent.spawn(TagA).id();
+        })
+        .id();
+    (parent, child)
+}
+
+#[test]
+fn despawner_despawn_with() {
+    for recursive in [false, true] {
+        let mut world = World::new();
+        let (parent, child) = spawn_family(&mut world);
+        world.run_system_once(move |mut despawner: EntityDespawner| {
+            despawner.despawn_with(parent, recursive);
+        });
+        assert!(world.get_entity(parent).is_none());
+        assert_eq!(world.get_entity(child).is_some(), !recursive);
+    }
+}
+
+#[test]
+fn despawner_despawn_many() {
+    let mut world = World::new();
+    world.spawn_batch([MyMarker; 3]);
+    world.spawn(TagA);
+    world.run_system_once(
+        |mut despawner: EntityDespawner, query: Query<Entity, With<MyMarker>>| {
+            let entities: Vec<Entity> = query.iter().collect();
+            assert_eq!(entities.len(), 3);
+            despawner.despawn_many(entities);
+        },
+    );
+    assert_eq!(count::<With<MyMarker>>(&mut world), 0);
+    assert_eq!(count::<With<TagA>>(&mut world), 1);
+
+    let (parent, child) = spawn_family(&mut world);
+    world.run_system_once(move |mut despawner: EntityDespawner| {
+        despawner.despawn_many_recursive([parent]);
+    });
+    assert!(world.get_entity(child).is_none());
+}
+
+fn singleton_app(policy: SingletonPolicy) -> App {
+    let mut app = App::new();
+    app.add_plugins(SingletonPlugin::<MyMarker>::new(policy));
+    app.world.spawn(MyMarker);
+    app.update();
+    app.world.spawn(MyMarker);
+    app.world.spawn(MyMarker);
+    app
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic]
+fn singleton_panic() {
+    singleton_app(SingletonPolicy::Panic).update();
+}
+
+#[test]
+fn singleton_warn() {
+    let mut app = singleton_app(SingletonPolicy::Warn);
+    app.update();
+    assert_eq!(count::<With<MyMarker>>(&mut app.world), 3);
+}
+
+#[test]
+fn singleton_despawn_extras() {
+    let mut app = singleton_app(SingletonPolicy::DespawnExtras);
+    app.update();
+    assert_eq!(count::<With<MyMarker>>(&mut app.world), 1);
+}
+
+#[test]
+fn singleton_despawn_extras_keeps_original() {
+    let mut app = App::new();
+    app.add_plugins(SingletonPlugin::<MyMarker>::new(
+        SingletonPolicy::DespawnExtras,
+    ));
+    // create the plain `MyMarker` archetype first, so the newcomer is iterated before the original
+    let placeholder = app.world.spawn(MyMarker).id();
+    app.world.despawn(placeholder);
+    let original = app.world.spawn((MyMarker, TagA)).id();
+    app.update();
+    let extra = app.world.spawn(MyMarker).id();
+    app.update();
+    assert!(app.world.get_entity(original).is_some());
+    assert!(app.world.get_entity(extra).is_none());
+}
+
+#[test]
+fn read_only_res() {
+    let mut world = World::new();
+    world.insert_resource(Score(4));
+    let value = world.run_system_once(|score: ReadOnlyRes<Score>| {
+        assert_eq!(score.get(), &Score(4));
+        let score: &Score = &score;
+        score.clone()
+    });
+    assert_eq!(value, Score(4));
+}
+
+fn limited_spawn_count(existing: usize, attempts: usize) -> usize {
+    let mut world = World::new();
+    world.insert_resource(SpawnLimit::<MyMarker>::new(3));
+    world.init_resource::<EntityCount<MyMarker>>();
+    world.spawn_batch(vec![MyMarker; existing]);
+    world.run_system_once(entity_count_system::<MyMarker>);
+    world.run_system_once(
+        move |mut spawner: EntitySpawner<MyMarker>,
+              limit: Res<SpawnLimit<MyMarker>>,
+              mut count: ResMut<EntityCount<MyMarker>>| {
+            for _ in 0..attempts {
+                spawner.spawn_default_limited(&limit, &mut count);
+            }
+        },
+    );
+    count::<With<MyMarker>>(&mut world)
+}
+
+#[test]
+fn spawn_limit() {
+    // below the limit
+    assert_eq!(limited_spawn_count(1, 1), 2);
+    // reaching the limit within a frame
+    assert_eq!(limited_spawn_count(1, 5), 3);
+    // at the limit
+    assert_eq!(limited_spawn_count(3, 1), 3);
+    // already above the limit
+    assert_eq!(limited_spawn_count(4, 1), 4);
+}
+
+#[test]
+fn spawn_on_cooldown() {
+    let mut app = App::new();
+    app.init_resource::<Time>()
+        .insert_resource(SpawnCooldown::<MyMarker>::new(Duration::from_secs(1)))
+        .add_systems(
+            Update,
+            (
+                spawn_cooldown_system::<MyMarker>,
+                |mut spawner: EntitySpawner<MyMarker>,
+                 mut cooldown: ResMut<SpawnCooldown<MyMarker>>| {
+                    spawner.spawn_default_on_cooldown(&mut cooldown);
+                },
+            )
+                .chain(),
+        );
+    let mut step = |millis| {
+        app.world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_millis(millis));
+        app.update();
+        count::<With<MyMarker>>(&mut app.world)
+    };
+    // ready immediately
+    assert_eq!(step(0), 1);
+    assert_eq!(step(400), 1);
+    assert_eq!(step(400), 1);
+    assert_eq!(step(400), 2);
+    assert_eq!(step(0), 2);
+    assert_eq!(step(1000), 3);
+}
+
+fn spawn_test_button(world: &mut World, style: ButtonStyle) -> Entity {
+    world.run_system_once_with(style, |In(style), mut commands: Commands| {
+        let mut button = Entity::PLACEHOLDER;
+        commands
+            .spawn(NodeBundle::default())
+            .with_children(|parent| {
+                button = spawn_button::<MyMarker>(parent, "Play", style);
+            });
+        button
+    })
+}
+
+#[test]
+fn spawn_button_returns_entity() {
+    let mut world = World::new();
+    let button = spawn_test_button(&mut world, ButtonStyle::default());
+    assert!(world.get::<Button>(button).is_some());
+    assert!(world.get::<MyMarker>(button).is_some());
+    assert_eq!(world.get::<Children>(button).unwrap().len(), 1);
+}
+
+#[test]
+fn button_interaction_colors() {
+    let mut world = World::new();
+    let style = ButtonStyle::default();
+    let (normal, hovered, pressed) = (style.background_color, style.hovered(), style.pressed());
+    let button = spawn_test_button(&mut world, style);
+    let mut set_interaction = |interaction| {
+        *world.get_mut::<Interaction>(button).unwrap() = interaction;
+        world.run_system_once(button_interaction_system::<MyMarker>);
+        world.get::<BackgroundColor>(button).unwrap().0
+    };
+    assert_eq!(set_interaction(Interaction::Hovered), hovered);
+    assert_eq!(set_interaction(Interaction::Pressed), pressed);
+    assert_eq!(set_interaction(Interaction::None), normal);
+}
+
+#[test]
+fn disabled_button_color() {
+    let mut world = World::new();
+    let style = ButtonStyle::default();
+    let (disabled, pressed) = (style.disabled_color, style.pressed());
+    let button = spawn_test_button(&mut world, style);
+    let set_disabled = |world: &mut World, value| {
+        world.run_system_once(move |mut commands: Commands| {
+            set_button_disabled(&mut commands, button, value);
+        });
+    };
+    set_disabled(&mut world, true);
+    assert!(world.get::<ButtonDisabled>(button).is_some());
+    assert_eq!(world.get::<BackgroundColor>(button).unwrap().0, disabled);
+
+    *world.get_mut::<Interaction>(button).unwrap() = Interaction::Pressed;
+    world.run_system_once(button_interaction_system::<MyMarker>);
+    assert_eq!(world.get::<BackgroundColor>(button).unwrap().0, disabled);
+
+    set_disabled(&mut world, false);
+    assert!(world.get::<ButtonDisabled>(button).is_none());
+    assert_eq!(world.get::<BackgroundColor>(button).unwrap().0, pressed);
+}
+
+#[test]
+fn button_colors_derive_from_background() {
+    let default = ButtonStyle::default();
+    let [r, ..] = default.hovered().as_rgba_f32();
+    assert!((r - 0.35).abs() < 1e-5);
+    let [r, ..] = default.pressed().as_rgba_f32();
+    assert!((r - 0.15).abs() < 1e-5);
+
+    let red = ButtonStyle {
+        background_color: Color::rgb(0.6, 0.0, 0.0),
+        ..Default::default()
+    };
+    let [r, g, b, _] = red.hovered().as_rgba_f32();
+    assert!(r > 0.6 && r > g && r > b);
+    let [r, ..] = red.pressed().as_rgba_f32();
+    assert!(r < 0.6);
+
+    let custom = ButtonStyle {
+        hovered_color: Some(Color::GOLD),
+        ..red
+    };
+    assert_eq!(custom.hovered(), Color::GOLD);
+}
+
+#[test]
+fn button_border_color() {
+    let mut world = World::new();
+    let button = spawn_test_button(
+        &mut world,
+        ButtonStyle {
+            border_color: Color::GOLD,
+            ..Default::default()
+        },
+    );
+    assert_eq!(world.get::<BorderColor>(button).unwrap().0, Color::GOLD);
+
+    let default = spawn_test_button(&mut world, ButtonStyle::default());
+    assert_eq!(
+        world.get::<BorderColor>(default).unwrap().0,
+        ButtonBundle::default().border_color.0
+    );
+}
+
+#[test]
+fn button_font() {
+    let font = Handle::<Font>::weak_from_u128(7);
+    let mut world = World::new();
+    let button = spawn_test_button(
+        &mut world,
+        ButtonStyle {
+            font: Some(font.clone()),
+            ..Default::default()
+        },
+    );
+    let text = world.get::<Children>(button).unwrap()[0];
+    assert_eq!(
+        world.get::<Text>(text).unwrap().sections[0].style.font,
+        font
+    );
+}
+
+#[test]
+fn button_menu() {
+    let mut world = World::new();
+    let root = world.run_system_once(|mut commands: Commands| {
+        let style = ButtonStyle::default();
+        ButtonMenu::new()
+            .button::<PlayButton>("Play", style.clone())
+            .button::<MyMarker>("Options", style.clone())
+            .button::<QuitButton>("Quit", style)
+            .spawn(&mut commands)
+    });
+    let children = world.get::<Children>(root).unwrap().to_vec();
+    assert_eq!(children.len(), 3);
+    assert!(children.iter().all(|&c| world.get::<Button>(c).is_some()));
+    assert!(world.get::<PlayButton>(children[0]).is_some());
+    assert!(world.get::<QuitButton>(children[2]).is_some());
+}
+
+#[test]
+fn button_click_events() {
+    let mut app = App::new();
+    app.add_event::<ButtonClicked<MyMarker>>()
+        .add_systems(Update, button_click_event_system::<MyMarker>);
+    let button = spawn_test_button(&mut app.world, ButtonStyle::default());
+    let mut reader = app
+        .world
+        .resource::<Events<ButtonClicked<MyMarker>>>()
+        .get_reader();
+    let mut press = |app: &mut App, interaction| {
+        app.world
+            .get_mut::<Interaction>(button)
+            .unwrap()
+            .set_if_neq(interaction);
+        app.update();
+        let events = app.world.resource::<Events<ButtonClicked<MyMarker>>>();
+        let clicked: Vec<Entity> = reader.read(events).map(|e| e.0).collect();
+        clicked
+    };
+    assert_eq!(press(&mut app, Interaction::Pressed), vec![button]);
+    // holding the button doesn't send more events
+    assert!(press(&mut app, Interaction::Pressed).is_empty());
+    assert!(press(&mut app, Interaction::Hovered).is_empty());
+    assert_eq!(press(&mut app, Interaction::Pressed), vec![button]);
+}
+
+#[test]
+fn disabled_button_sends_no_click() {
+    let mut app = App::new();
+    app.add_event::<ButtonClicked<MyMarker>>()
+        .add_systems(Update, button_click_event_system::<MyMarker>);
+    let button = spawn_test_button(&mut app.world, ButtonStyle::default());
+    app.world.entity_mut(button).insert(ButtonDisabled);
+    *app.world.get_mut::<Interaction>(button).unwrap() = Interaction::Pressed;
+    app.update();
+    assert!(app
+        .world
+        .resource::<Events<ButtonClicked<MyMarker>>>()
+        .is_empty());
+}
+
+#[test]
+fn state_resource_given_many() {
+    let mut app = App::new();
+    app.add_state::<GameState>()
+        .add_plugins(state_resource_plugin_given_many(
+            [GameState::Playing, GameState::Paused],
+            Score(0),
+        ));
+    app.update();
+    assert!(!app.world.contains_resource::<Score>());
+
+    set_state(&mut app, GameState::Playing);
+    assert_eq!(app.world.resource::<Score>(), &Score(0));
+    app.world.resource_mut::<Score>().0 = 5;
+
+    set_state(&mut app, GameState::Paused);
+    assert_eq!(app.world.resource::<Score>(), &Score(5));
+    set_state(&mut app, GameState::Playing);
+    assert_eq!(app.world.resource::<Score>(), &Score(5));
+
+    set_state(&mut app, GameState::Menu);
+    assert!(!app.world.contains_resource::<Score>());
+    set_state(&mut app, GameState::Paused);
+    assert_eq!(app.world.resource::<Score>(), &Score(0));
+}
+
+#[test]
+fn persistent_state_resource() {
+    let mut app = App::new();
+    app.add_state::<GameState>()
+        .add_plugins(persistent_state_resource_plugin(
+            GameState::Playing,
+            Score(0),
+        ));
+    app.update();
+    assert!(!app.world.contains_resource::<Score>());
+
+    set_state(&mut app, GameState::Playing);
+    app.world.resource_mut::<Score>().0 = 5;
+    set_state(&mut app, GameState::Paused);
+    assert_eq!(app.world.resource::<Score>(), &Score(5));
+    set_state(&mut app, GameState::Playing);
+    assert_eq!(app.world.resource::<Score>(), &Score(5));
+}
+
+#[test]
+fn state_entity_cleanup() {
+    let mut app = App::new();
+    app.add_state::<GameState>()
+        .add_plugins(state_entity_cleanup_plugin::<_, MyMarker>(GameState::Menu));
+    app.update();
+    app.world.spawn_batch([MyMarker; 3]);
+    app.world.spawn(TagA);
+    app.update();
+    assert_eq!(count::<With<MyMarker>>(&mut app.world), 3);
+
+    set_state(&mut app, GameState::Playing);
+    assert_eq!(count::<With<MyMarker>>(&mut app.world), 0);
+    assert_eq!(count::<With<TagA>>(&mut app.world), 1);
+}
+
+#[test]
+fn once_closure_plugin() {
+    fn score_plugin() -> impl Plugin {
+        OnceClosurePlugin::new("score", |app: &mut App| {
+            app.add_systems(Update, |mut score: ResMut<Score>| score.0 += 1);
+        })
+    }
+    let mut app = App::new();
+    app.init_resource::<Score>()
+        .add_plugins(score_plugin())
+        .add_plugins(score_plugin());
+    app.update();
+    assert_eq!(app.world.resource::<Score>(), &Score(1));
+}
+
+#[test]
+fn resource_handle_get() {
+    let mut world = World::new();
+    world.insert_resource(Score(3));
+    let value = world.run_system_once(|mut score: ResourceHandle<Score>| {
+        let value = score.get().cloned();
+        score.remove();
+        value
+    });
+    assert_eq!(value, Some(Score(3)));
+    assert!(!world.contains_resource::<Score>());
+    let value = world.run_system_once(|score: ResourceHandle<Score>| score.get().cloned());
+    assert_eq!(value, None);
+}
+
+#[test]
+fn resource_reset() {
+    let mut world = World::new();
+    world.insert_resource(Score(3));
+    world.run_system_once(resource_reset_system::<Score>);
+    assert_eq!(world.resource::<Score>(), &Score::default());
+
+    world.remove_resource::<Score>();
+    world.run_system_once(resource_reset_system::<Score>);
+    assert_eq!(world.resource::<Score>(), &Score::default());
+}
+
+#[test]
+fn square_sprite_grid_xy() {
+    let sprite = SquareSprite::builder()
+        .at(2.0, 3.0)
+        .grid(10.0)
+        .grid_xy(Vec2::new(64.0, 32.0))
+        .build();
+    assert_eq!(
+        square_sprite(sprite).transform.translation,
+        Vec3::new(128.0, 96.0, 0.0)
+    );
+}
+
+#[test]
+fn sprite_layers() {
+    let layers = [Layer::Background, Layer::Tiles, Layer::Entities, Layer::Ui];
+    assert!(layers.windows(2).all(|w| w[0].z() < w[1].z()));
+    assert_eq!(
+        SquareSprite::default().on_layer(Layer::Entities).z,
+        Layer::Entities.z()
+    );
+    assert_eq!(
+        SquareSprite::builder().on_layer(Layer::Tiles).build().z,
+        10.0
+    );
+}
+
+#[test]
+fn square_sprite_texture() {
+    assert_eq!(
+        square_sprite(SquareSprite::default()).texture,
+        Handle::default()
+    );
+    let texture = Handle::<Image>::weak_from_u128(11);
+    let bundle = square_sprite(SquareSprite::builder().texture(texture.clone()).build());
+    assert_eq!(bundle.texture, texture);
+}
+
+#[test]
+fn reset_entity_respawns() {
+    let mut world = World::new();
+    let old = world.spawn((MyMarker, Name::new("dead"))).id();
+    world.run_system_once(
+        move |mut despawner: EntityDespawner, mut spawner: EntitySpawner<MyMarker>| {
+            reset_entity(&mut despawner, &mut spawner, old);
+        },
+    );
+    assert!(world.get_entity(old).is_none());
+    assert_eq!(count::<With<MyMarker>>(&mut world), 1);
+    assert_eq!(count::<With<Name>>(&mut world), 0);
+}
+
+#[derive(Resource)]
+struct Dirty(bool);
+
+impl ShouldCleanup for Dirty {
+    fn should_cleanup(&self) -> bool {
+        self.0
+    }
+}
+
+#[test]
+fn conditional_entity_cleanup() {
+    for dirty in [false, true] {
+        let mut world = World::new();
+        world.insert_resource(Dirty(dirty));
+        world.spawn_batch([MyMarker; 2]);
+        world.run_system_once(conditional_entity_cleanup_system::<MyMarker, Dirty>);
+        assert_eq!(
+            count::<With<MyMarker>>(&mut world),
+            if dirty { 0 } else { 2 }
+        );
+    }
+}
+
+#[test]
+fn spawner_spawn_default_at() {
+    let mut world = World::new();
+    let entity = world.run_system_once(|mut spawner: EntitySpawner<MyMarker>| {
+        spawner
+            .spawn_default_at(SquareSprite::builder().at(1.0, 2.0).build())
+            .id()
+    });
+    assert!(world.get::<MyMarker>(entity).is_some());
+    assert!(world.get::<Sprite>(entity).is_some());
+    assert_eq!(
+        world.get::<Transform>(entity).unwrap().translation,
+        Vec3::new(100.0, 200.0, 0.0)
+    );
+}
+
+struct Player;
+struct Enemy;
+
+#[test]
+fn marker_components_generic() {
+    let mut world = World::new();
+    world.spawn(Tagged::<Player>::default());
+    world.spawn(Tagged::<Player>::default());
+    world.spawn(Tagged::<Enemy>::default());
+    assert_eq!(count::<With<Tagged<Player>>>(&mut world), 2);
+    assert_eq!(count::<With<Tagged<Enemy>>>(&mut world), 1);
+    assert!(Tagged::<Player>::default() == Tagged::<Player>::default());
+}
+
+#[test]
+fn marker_components_extra_derives() {
+    assert_eq!(format!("{DebugA:?} {DebugB:?}"), "DebugA DebugB");
+}
+
+#[test]
+fn entity_count_logger() {
+    let mut app = App::new();
+    app.init_resource::<Time>()
+        .add_plugins(EntityCountLoggerPlugin::<MyMarker>::new(
+            Duration::from_millis(500),
+        ));
+    app.world.spawn_batch([MyMarker; 3]);
+    app.world.spawn(TagA);
+    app.update();
+    assert_eq!(app.world.resource::<EntityCount<MyMarker>>().0, 3);
+
+    let entity = app.world.spawn(MyMarker).id();
+    app.update();
+    assert_eq!(app.world.resource::<EntityCount<MyMarker>>().0, 4);
+    app.world.despawn(entity);
+    app.world
+        .resource_mut::<Time>()
+        .advance_by(Duration::from_secs(1));
+    app.update();
+    assert_eq!(app.world.resource::<EntityCount<MyMarker>>().0, 3);
+}
+
+#[test]
+fn lifetime_despawns() {
+    let mut app = App::new();
+    app.init_resource::<Time>()
+        .add_systems(Update, lifetime_system);
+    let entity = app.world.spawn(with_lifetime(Duration::from_secs(1))).id();
+    for _ in 0..3 {
+        advance_time(&mut app, 300);
+        assert!(app.world.get_entity(entity).is_some());
+    }
+    advance_time(&mut app, 300);
+    assert!(app.world.get_entity(entity).is_none());
+}
+
+#[test]
+fn resource_handle_get_or_insert_with() {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut world = World::new();
+    for _ in 0..2 {
+        let system_calls = calls.clone();
+        world.run_system_once(move |mut score: ResourceHandle<Score>| {
+            let calls = system_calls.clone();
+            score.get_or_insert_with(move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Score(9)
+            });
+        });
+        assert_eq!(world.resource::<Score>(), &Score(9));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
+
+#[derive(Resource, Debug, PartialEq)]
+struct DoubleScore(u32);
+
+#[test]
+fn state_resource_with() {
+    let mut app = App::new();
+    app.add_state::<GameState>()
+        .insert_resource(Score(21))
+        .add_plugins(state_resource_plugin_with(GameState::Playing, |world| {
+            DoubleScore(world.resource::<Score>().0 * 2)
+        }));
+    app.update();
+    assert!(!app.world.contains_resource::<DoubleScore>());
+
+    set_state(&mut app, GameState::Playing);
+    assert_eq!(app.world.resource::<DoubleScore>(), &DoubleScore(42));
+    set_state(&mut app, GameState::Menu);
+    assert!(!app.world.contains_resource::<DoubleScore>());
+}
+
+#[test]
+fn count_param() {
+    let mut world = World::new();
+    world.spawn_batch([MyMarker; 5]);
+    world.spawn_batch([(MyMarker, TagA); 2]);
+    world.spawn(TagA);
+    let counts = world.run_system_once(
+        |all: Count<MyMarker>, tagged: Count<MyMarker, With<TagA>>| (all.get(), tagged.get()),
+    );
+    assert_eq!(counts, (7, 2));
+}
+
+#[derive(Component)]
+struct ClickSound(&'static str);
+
+#[test]
+fn spawn_button_with_extra() {
+    let mut world = World::new();
+    let button = world.run_system_once(|mut commands: Commands| {
+        let mut button = Entity::PLACEHOLDER;
+        commands
+            .spawn(NodeBundle::default())
+            .with_children(|parent| {
+                button = spawn_button_with::<MyMarker>(
+                    parent,
+                    "Buy",
+                    ButtonStyle::default(),
+                    ClickSound("coin.ogg"),
+                );
+            });
+        button
+    });
+    assert_eq!(world.get::<ClickSound>(button).unwrap().0, "coin.ogg");
+    assert!(world.get::<MyMarker>(button).is_some());
+}
+
+#[test]
+fn despawner_despawn_if_exists() {
+    let mut world = World::new();
+    let entity = world.spawn(MyMarker).id();
+    world.run_system_once(move |mut despawner: EntityDespawner| {
+        despawner.despawn_if_exists(entity);
+        despawner.despawn_if_exists(entity);
+    });
+    assert!(world.get_entity(entity).is_none());
+}
+
+#[test]
+fn square_sprite_origin() {
+    let origin = Vec2::new(-300.0, 150.0);
+    let sprite = SquareSprite::builder()
+        .at(2.0, -1.0)
+        .grid(32.0)
+        .origin(origin)
+        .z(1.0)
+        .build();
+    assert_eq!(
+        square_sprite(sprite).transform.translation,
+        (origin + 32.0 * Vec2::new(2.0, -1.0)).extend(1.0)
+    );
+}
+
+#[test]
+fn set_resource() {
+    let mut app = App::new();
+    app.add_systems(Update, set_resource_system(Score(8)));
+    app.update();
+    assert_eq!(app.world.resource::<Score>(), &Score(8));
+    app.world.resource_mut::<Score>().0 = 0;
+    app.update();
+    assert_eq!(app.world.resource::<Score>(), &Score(8));
+}
+
+#[test]
+fn closure_plugin_then() {
+    let mut app = App::new();
+    app.add_plugins(
+        closure_plugin(|app: &mut App| {
+            app.insert_resource(Score(1));
+        })
+        .then(closure_plugin(|app: &mut App| {
+            let score = app.world.resource::<Score>().0;
+            app.insert_resource(DoubleScore(score * 2));
+        }))
+        .then(closure_plugin(|app: &mut App| {
+            app.insert_resource(Paused(true));
+        })),
+    );
+    assert_eq!(app.world.resource::<Score>(), &Score(1));
+    assert_eq!(app.world.resource::<DoubleScore>(), &DoubleScore(2));
+    assert_eq!(app.world.resource::<Paused>(), &Paused(true));
+}
+
+#[test]
+fn button_style_presets() {
+    let (default, primary, danger, small) = (
+        ButtonStyle::default(),
+        ButtonStyle::primary(),
+        ButtonStyle::danger(),
+        ButtonStyle::small(),
+    );
+    let backgrounds = [
+        default.background_color,
+        primary.background_color,
+        danger.background_color,
+    ];
+    for (i, a) in backgrounds.iter().enumerate() {
+        for b in &backgrounds[i + 1..] {
+            assert_ne!(a, b);
+        }
+    }
+    assert!(danger.background_color.r() > danger.background_color.b());
+    assert!(primary.background_color.b() > primary.background_color.r());
+    assert_eq!(primary.text_color, Color::WHITE);
+    assert_eq!(small.font_size, 18.0);
+    assert_eq!(small.width, Val::Px(100.0));
+    assert!(small.font_size < default.font_size);
+}
+
+#[test]
+fn spawn_default_indexed_assigns_incrementing_indices() {
+    let mut world = World::new();
+    let entities = world.run_system_once(|mut spawner: EntitySpawner<MyMarker>| {
+        [(); 3].map(|_| spawner.spawn_default_indexed().id())
+    });
+    let indices = entities.map(|entity| world.get::<IndexedMarker>(entity).unwrap().0);
+    assert_eq!(indices, [0, 1, 2]);
+    assert_eq!(world.resource::<SpawnCounter<MyMarker>>().0, 3);
+}
+
+#[derive(Resource, Default)]
+struct ConditionRuns {
+    present: u32,
+    absent: u32,
+}
+
+#[test]
+fn resource_exists_and_absent_conditions() {
+    let mut app = App::new();
+    app.init_resource::<ConditionRuns>().add_systems(
+        Update,
+        (
+            (|mut runs: ResMut<ConditionRuns>| runs.present += 1)
+                .run_if(resource_exists_condition::<Score>()),
+            (|mut runs: ResMut<ConditionRuns>| runs.absent += 1).run_if(resource_absent::<Score>()),
+        ),
+    );
+    app.update();
+    app.world.insert_resource(Score(0));
+    app.update();
+    app.update();
+    app.world.remove_resource::<Score>();
+    app.update();
+    let runs = app.world.resource::<ConditionRuns>();
+    assert_eq!((runs.present, runs.absent), (2, 2));
+}
+
+#[test]
+fn despawner_despawn_children() {
+    let mut world = World::new();
+    let (parent, child) = spawn_family(&mut world);
+    let grandchild = world.spawn(TagB).set_parent(child).id();
+    world.run_system_once(move |mut despawner: EntityDespawner| {
+        despawner.despawn_children(parent);
+    });
+    assert!(world.get_entity(parent).is_some());
+    assert!(world.get_entity(child).is_none());
+    assert!(world.get_entity(grandchild).is_none());
+    assert!(world
+        .get::<Children>(parent)
+        .is_none_or(|children| children.is_empty()));
+}
+
+#[test]
+fn square_sprite_z_slot() {
+    let sprite = SquareSprite::default().z_slot(3);
+    assert_eq!(sprite.z, 3.0);
+    let built = SquareSprite::builder().z(0.4).z_slot(-2).build();
+    assert_eq!(built.translation().z, -2.0);
+}
+
+#[test]
+fn zero_grid_round_trips() {
+    for (x, y) in [(3, -2), (0, 0), (-7, 11)] {
+        let world = grid_to_world(x as f32, y as f32, 0.0);
+        assert_eq!(world_to_grid(world, 0.0), IVec2::new(x, y));
+    }
+    assert_eq!(world_to_grid(Vec2::new(2.5, -0.5), 0.0), IVec2::new(2, -1));
+    let rect = rect_sprite(RectSprite {
+        x: 4.0,
+        y: -3.0,
+        grid: 0.0,
+        ..Default::default()
+    });
+    let square = square_sprite(SquareSprite {
+        x: 4.0,
+        y: -3.0,
+        grid: 0.0,
+        ..Default::default()
+    });
+    assert_eq!(rect.transform.translation, square.transform.translation);
+}
+
+#[test]
+fn square_sprite_zero_grid_uses_raw_coordinates() {
+    let sprite = SquareSprite {
+        x: 12.0,
+        y: -5.0,
+        grid: 0.0,
+        ..Default::default()
+    };
+    let translation = sprite.translation();
+    assert!(translation.is_finite());
+    assert_eq!(translation.truncate(), Vec2::new(12.0, -5.0));
+
+    let sprite = SquareSprite {
+        x: 2.0,
+        y: 3.0,
+        grid_xy: Some(Vec2::new(0.0, 10.0)),
+        ..Default::default()
+    };
+    assert_eq!(sprite.translation().truncate(), Vec2::new(2.0, 30.0));
+}
+
+#[test]
+fn respawn_on_resource_change() {
+    let mut app = App::new();
+    app.insert_resource(Score(0))
+        .add_systems(Update, respawn_on_resource_change_system::<MyMarker, Score>);
+    let original = app.world.spawn(MyMarker).id();
+    app.update();
+    assert!(app.world.get_entity(original).is_some());
+
+    app.world.resource_mut::<Score>().0 += 1;
+    app.update();
+    assert!(app.world.get_entity(original).is_none());
+    assert_eq!(count::<With<MyMarker>>(&mut app.world), 1);
+    let rebuilt = app
+        .world
+        .query_filtered::<Entity, With<MyMarker>>()
+        .single(&app.world);
+
+    app.update();
+    assert!(app.world.get_entity(rebuilt).is_some());
+
+    app.world.resource_mut::<Score>().0 += 1;
+    app.update();
+    assert!(app.world.get_entity(rebuilt).is_none());
+    assert_eq!(count::<With<MyMarker>>(&mut app.world), 1);
+}
+
+#[test]
+fn toggle_triggers_respawn_on_resource_change() {
+    let mut app = App::new();
+    app.init_resource::<Paused>().add_systems(
+        Update,
+        respawn_on_resource_change_system::<MyMarker, Paused>,
+    );
+    let original = app.world.spawn(MyMarker).id();
+    app.update();
+    assert!(app.world.get_entity(original).is_some());
+
+    app.world
+        .run_system_once(|mut paused: ResourceHandle<Paused>| paused.toggle());
+    app.update();
+    assert!(app.world.get_entity(original).is_none());
+    assert_eq!(count::<With<MyMarker>>(&mut app.world), 1);
+}
+
+#[test]
+fn state_transition_button() {
+    let mut app = App::new();
+    app.add_state::<GameState>().add_systems(
+        Update,
+        state_transition_button_system::<MyMarker, _>(GameState::Playing),
+    );
+    let button = spawn_test_button(&mut app.world, ButtonStyle::default());
+    app.update();
+    assert_eq!(app.world.resource::<NextState<GameState>>().0, None);
+
+    *app.world.get_mut::<Interaction>(button).unwrap() = Interaction::Pressed;
+    app.world.run_schedule(Update);
+    assert_eq!(
+        app.world.resource::<NextState<GameState>>().0,
+        Some(GameState::Playing)
+    );
+    app.update();
+    assert_eq!(
+        *app.world.resource::<State<GameState>>().get(),
+        GameState::Playing
+    );
+}
+
+#[test]
+fn resource_handle_snapshot_restore() {
+    let mut world = World::new();
+    world.insert_resource(Score(5));
+    world.run_system_once(|mut score: ResourceHandle<Score>| {
+        score.snapshot();
+        score.modify(|score| score.0 = 40);
+    });
+    assert_eq!(world.resource::<Score>(), &Score(40));
+    assert_eq!(world.resource::<ResourceSnapshot<Score>>().0, Score(5));
+
+    world.run_system_once(|mut score: ResourceHandle<Score>| score.restore());
+    assert_eq!(world.resource::<Score>(), &Score(5));
+}
+
+#[test]
+fn resource_handle_restore_without_snapshot() {
+    let mut world = World::new();
+    world.insert_resource(Score(7));
+    world.run_system_once(|mut score: ResourceHandle<Score>| score.restore());
+    assert_eq!(world.resource::<Score>(), &Score(7));
+}
+
+#[test]
+fn swap_marker_replaces_component() {
+    let mut world = World::new();
+    let entity = world.spawn((TagA, MyMarker)).id();
+    world.run_system_once(move |mut editor: EntityEditor| {
+        editor.swap_marker::<TagA, TagB>(entity);
+    });
+    assert!(world.get::<TagA>(entity).is_none());
+    assert!(world.get::<TagB>(entity).is_some());
+    assert!(world.get::<MyMarker>(entity).is_some());
+
+    world.run_system_once(move |mut commands: Commands| {
+        swap_marker::<TagB, TagC>(&mut commands, entity);
+    });
+    assert!(world.get::<TagB>(entity).is_none());
+    assert!(world.get::<TagC>(entity).is_some());
+}
+
+#[test]
+fn spawn_grid_fills_block() {
+    let mut world = World::new();
+    world.run_system_once(|mut spawner: EntitySpawner<MyMarker>| {
+        let template = SquareSprite {
+            x: 1.0,
+            y: 2.0,
+            grid: 10.0,
+            ..Default::default()
+        };
+        spawn_grid(&mut spawner, 4, 3, template);
+    });
+    let translations: Vec<Vec3> = world
+        .query_filtered::<&Transform, With<MyMarker>>()
+        .iter(&world)
+        .map(|transform| transform.translation)
+        .collect();
+    assert_eq!(translations.len(), 12);
+    for corner in [
+        Vec3::new(10.0, 20.0, 0.0),
+        Vec3::new(40.0, 20.0, 0.0),
+        Vec3::new(10.0, 40.0, 0.0),
+        Vec3::new(40.0, 40.0, 0.0),
+    ] {
+        assert!(translations.contains(&corner), "missing {corner}");
+    }
+}
+
+#[test]
+fn spawn_grid_keeps_border() {
+    let mut world = World::new();
+    world.run_system_once(|mut spawner: EntitySpawner<MyMarker>| {
+        let template = SquareSprite::builder()
+            .size(8.0)
+            .border(1.0, Color::WHITE)
+            .build();
+        spawn_grid(&mut spawner, 2, 2, template);
+    });
+    let cells: Vec<Entity> = world
+        .query_filtered::<Entity, With<MyMarker>>()
+        .iter(&world)
+        .collect();
+    assert_eq!(cells.len(), 4);
+    for cell in cells {
+        let outline = world.get::<Children>(cell).unwrap()[0];
+        assert_eq!(
+            world.get::<Sprite>(outline).unwrap().custom_size,
+            Some(Vec2::splat(10.0))
+        );
+    }
+}
+
+#[test]
+fn state_visibility() {
+    let mut app = App::new();
+    app.add_state::<GameState>()
+        .add_plugins(state_visibility_plugin::<_, MyMarker>(GameState::Playing));
+    let shown = app.world.spawn((MyMarker, Visibility::Hidden)).id();
+    let other = app.world.spawn((TagA, Visibility::Hidden)).id();
+    app.update();
+    assert_eq!(
+        app.world.get::<Visibility>(shown),
+        Some(&Visibility::Hidden)
+    );
+
+    set_state(&mut app, GameState::Playing);
+    assert_eq!(
+        app.world.get::<Visibility>(shown),
+        Some(&Visibility::Visible)
+    );
+    assert_eq!(
+        app.world.get::<Visibility>(other),
+        Some(&Visibility::Hidden)
+    );
+
+    set_state(&mut app, GameState::Paused);
+    assert_eq!(
+        app.world.get::<Visibility>(shown),
+        Some(&Visibility::Hidden)
+    );
+    assert!(app.world.get_entity(shown).is_some());
+}
+
+#[test]
+fn ui_root_fills_screen() {
+    let mut world = World::new();
+    let (root, button) = world.run_system_once(|mut commands: Commands| {
+        let mut button = Entity::PLACEHOLDER;
+        let root = spawn_ui_root(&mut commands, JustifyContent::Center, AlignItems::End)
+            .with_children(|parent| {
+                button = spawn_button::<PlayButton>(parent, "Play", ButtonStyle::default());
+            })
+            .id();
+        (root, button)
+    });
+    let style = world.get::<Style>(root).unwrap();
+    assert_eq!(style.width, Val::Percent(100.0));
+    assert_eq!(style.height, Val::Percent(100.0));
+    assert_eq!(style.justify_content, JustifyContent::Center);
+    assert_eq!(style.align_items, AlignItems::End);
+    assert_eq!(world.get::<Parent>(button).unwrap().get(), root);
+}
+
+#[test]
+fn resource_change_logger() {
+    let mut app = App::new();
+    app.add_plugins(ResourceChangeLoggerPlugin::<Score>::default());
+    let changes = |app: &App| app.world.resource::<ResourceChangeCount<Score>>().0;
+    app.update();
+    assert_eq!(changes(&app), 0);
+
+    app.world.insert_resource(Score(1));
+    app.update();
+    assert_eq!(changes(&app), 1);
+    app.update();
+    assert_eq!(changes(&app), 1);
+
+    app.world.resource_mut::<Score>().0 = 2;
+    app.update();
+    app.update();
+    assert_eq!(changes(&app), 2);
+}
+
+#[test]
+fn offscreen_cleanup() {
+    let mut world = World::new();
+    world.insert_resource(CleanupBounds(Rect::new(-100.0, -50.0, 100.0, 50.0)));
+    let spawn_at =
+        |world: &mut World, x, y| world.spawn((MyMarker, Transform::from_xyz(x, y, 5.0))).id();
+    let inside = spawn_at(&mut world, 0.0, 0.0);
+    let edge = spawn_at(&mut world, 100.0, -50.0);
+    let right = spawn_at(&mut world, 150.0, 0.0);
+    let below = spawn_at(&mut world, 0.0, -80.0);
+    let unmarked = world.spawn(Transform::from_xyz(500.0, 0.0, 0.0)).id();
+    world.run_system_once(offscreen_cleanup_system::<MyMarker>);
+    assert!(world.get_entity(inside).is_some());
+    assert!(world.get_entity(edge).is_some());
+    assert!(world.get_entity(right).is_none());
+    assert!(world.get_entity(below).is_none());
+    assert!(world.get_entity(unmarked).is_some());
+}
+
+#[test]
+fn spawn_default_under_parent() {
+    let mut world = World::new();
+    let parent = world.spawn(TagA).id();
+    let child = world.run_system_once(move |mut spawner: EntitySpawner<MyMarker>| {
+        spawner.spawn_default_under(parent).insert(TagB).id()
+    });
+    assert!(world.get::<Children>(parent).unwrap().contains(&child));
+    assert!(world.get::<MyMarker>(child).is_some());
+    assert!(world.get::<TagB>(child).is_some());
+}
+
+#[test]
+fn state_setter_queues_transition() {
+    let mut app = App::new();
+    app.add_state::<GameState>();
+    app.world
+        .run_system_once(|mut setter: StateSetter<GameState>| setter.set(GameState::Paused));
+    assert_eq!(
+        app.world.resource::<NextState<GameState>>().0,
+        Some(GameState::Paused)
+    );
+    app.update();
+    assert_eq!(
+        *app.world.resource::<State<GameState>>().get(),
+        GameState::Paused
+    );
+}
+
+#[test]
+fn death_cleanup_despawns_dead() {
+    let mut world = World::new();
+    let (alive, dead, overkill) = (
+        world.spawn(Health::new(1.0)).id(),
+        world.spawn(Health::new(0.0)).id(),
+        world.spawn(Health::new(-5.0)).id(),
+    );
+    let child = world.spawn(TagA).set_parent(dead).id();
+    world.run_system_once(death_cleanup_system);
+    assert!(world.get_entity(alive).is_some());
+    assert!(world.get_entity(dead).is_none());
+    assert!(world.get_entity(overkill).is_none());
+    assert!(world.get_entity(child).is_none());
+}
+
+#[test]
+fn death_cleanup_sends_died_once() {
+    let mut app = App::new();
+    app.add_event::<Died>()
+        .add_systems(Update, death_cleanup_system);
+    let player = app.world.spawn(Health::new(3.0)).id();
+    let mut reader = app.world.resource::<Events<Died>>().get_reader();
+    let mut deaths = Vec::new();
+    for damage in [1.0, 2.0, 0.0, 0.0] {
+        if let Some(mut health) = app.world.get_mut::<Health>(player) {
+            health.current -= damage;
+        }
+        app.update();
+        deaths.extend(reader.read(app.world.resource::<Events<Died>>()).copied());
+    }
+    assert_eq!(deaths, vec![Died(player)]);
+    assert!(app.world.get_entity(player).is_none());
+}
+
+#[test]
+fn restyle_button_applies_new_style() {
+    let mut world = World::new();
+    let button = spawn_test_button(&mut world, ButtonStyle::default());
+    let style = ButtonStyle {
+        text_color: Color::YELLOW,
+        ..ButtonStyle::danger()
+    };
+    world.run_system_once_with(style.clone(), move |In(style), mut commands: Commands| {
+        restyle_button(&mut commands, button, style);
+    });
+    assert_eq!(
+        world.get::<BackgroundColor>(button).unwrap().0,
+        style.background_color
+    );
+    assert_eq!(
+        world.get::<ButtonColors>(button).unwrap().hovered,
+        style.hovered()
+    );
+    let text = world.get::<Children>(button).unwrap()[0];
+    let section = &world.get::<Text>(text).unwrap().sections[0];
+    assert_eq!(section.style.color, Color::YELLOW);
+    assert_eq!(section.value, "Play");
+
+    let small = ButtonStyle::small();
+    world.run_system_once_with(small.clone(), move |In(style), mut commands: Commands| {
+        restyle_button(&mut commands, button, style);
+    });
+    assert_eq!(world.get::<Style>(button).unwrap().width, small.width);
+    let section = &world.get::<Text>(text).unwrap().sections[0];
+    assert_eq!(section.style.font_size, small.font_size);
+}
+
+#[test]
+fn versioned_resource_counts_replacements() {
+    let mut world = World::new();
+    let set = |world: &mut World, value: u32| {
+        world.run_system_once_with(
+            value,
+            |In(value), mut score: ResourceHandle<Versioned<u32>>| {
+                score.set_versioned(value);
+            },
+        );
+    };
+    set(&mut world, 1);
+    assert_eq!(world.resource::<Versioned<u32>>().version(), 0);
+    set(&mut world, 5);
+    set(&mut world, 5);
+    let versioned = world.resource::<Versioned<u32>>();
+    assert_eq!(versioned.version(), 2);
+    assert_eq!(*versioned.get(), 5);
+}
+
+#[derive(Component, Debug, PartialEq)]
+struct KeyIndex(usize);
+
+#[test]
+fn spawn_button_grid_spawns_each_label() {
+    let mut world = World::new();
+    let grid = world.run_system_once(|mut commands: Commands| {
+        let mut grid = Entity::PLACEHOLDER;
+        commands
+            .spawn(NodeBundle::default())
+            .with_children(|parent| {
+                let labels = ["1", "2", "3", "4", "5", "6", "7", "8", "9"];
+                grid = spawn_button_grid_with::<MyMarker, _>(
+                    parent,
+                    &labels,
+                    3,
+                    ButtonStyle::small(),
+                    KeyIndex,
+                );
+            });
+        grid
+    });
+    let style = world.get::<Style>(grid).unwrap();
+    assert_eq!(style.display, Display::Grid);
+    assert_eq!(
+        style.grid_template_columns,
+        RepeatedGridTrack::auto::<Vec<_>>(3)
+    );
+    let buttons = world.get::<Children>(grid).unwrap().to_vec();
+    assert_eq!(buttons.len(), 9);
+    for (index, button) in buttons.into_iter().enumerate() {
+        assert!(world.get::<MyMarker>(button).is_some());
+        assert_eq!(world.get::<KeyIndex>(button), Some(&KeyIndex(index)));
+    }
+}
+
+#[test]
+fn despawner_despawn_matching() {
+    let mut world = World::new();
+    let tagged = world.spawn((MyMarker, TagA)).id();
+    let untagged = world.spawn(MyMarker).id();
+    let other = world.spawn(TagA).id();
+    world.run_system_once(
+        |mut despawner: EntityDespawner, query: Query<Entity, (With<MyMarker>, With<TagA>)>| {
+            despawner.despawn_matching(&query);
+        },
+    );
+    assert!(world.get_entity(tagged).is_none());
+    assert!(world.get_entity(untagged).is_some());
+    assert!(world.get_entity(other).is_some());
+}
+
+#[test]
+fn game_time_respects_pause() {
+    let mut world = World::new();
+    world.init_resource::<Time>();
+    world.init_resource::<GameTime>();
+    let tick = |world: &mut World, millis| {
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_millis(millis));
+        world.run_system_once(advance_game_time_system);
+    };
+    tick(&mut world, 100);
+    assert_eq!(
+        world.resource::<GameTime>().elapsed(),
+        Duration::from_millis(100)
+    );
+
+    world.insert_resource(Paused(true));
+    tick(&mut world, 250);
+    let game_time = world.resource::<GameTime>();
+    assert_eq!(game_time.elapsed(), Duration::from_millis(100));
+    assert_eq!(game_time.delta(), Duration::ZERO);
+
+    world.run_system_once(|mut paused: ResourceHandle<Paused>| paused.toggle());
+    tick(&mut world, 50);
+    let game_time = world.resource::<GameTime>();
+    assert_eq!(game_time.elapsed(), Duration::from_millis(150));
+    assert_eq!(game_time.delta(), Duration::from_millis(50));
+}
+
+#[test]
+fn despawner_despawn_then() {
+    let mut world = World::new();
+    world.insert_resource(Score(3));
+    let enemy = world
+        .spawn((MyMarker, Transform::from_xyz(4.0, 2.0, 0.0)))
+        .id();
+    world.run_system_once(
+        move |mut despawner: EntityDespawner, query: Query<&Transform>| {
+            let last_position = query.get(enemy).unwrap().translation;
+            despawner.despawn_then(enemy, move |world: &mut World| {
+                assert!(world.get_entity(enemy).is_none());
+                world.resource_mut::<Score>().0 -= 1;
+                world.spawn((TagA, Transform::from_translation(last_position)));
+            });
+        },
+    );
+    assert!(world.get_entity(enemy).is_none());
+    assert_eq!(world.resource::<Score>(), &Score(2));
+    let effect = world
+        .query_filtered::<&Transform, With<TagA>>()
+        .single(&world);
+    assert_eq!(effect.translation, Vec3::new(4.0, 2.0, 0.0));
+}
+
+#[test]
+fn square_sprite_border_child() {
+    let mut world = World::new();
+    let (plain, outlined) = world.run_system_once(|mut spawner: EntitySpawner<MyMarker>| {
+        let plain = spawner.spawn_default_at(SquareSprite::default()).id();
+        let sprite = SquareSprite::builder()
+            .size(40.0)
+            .border(3.0, Color::WHITE)
+            .build();
+        (plain, spawner.spawn_default_at(sprite).id())
+    });
+    assert!(world.get::<Children>(plain).is_none());
+
+    let children = world.get::<Children>(outlined).unwrap();
+    assert_eq!(children.len(), 1);
+    let outline = children[0];
+    let inner = world.get::<Sprite>(outlined).unwrap().custom_size.unwrap();
+    let outer = world.get::<Sprite>(outline).unwrap();
+    assert_eq!(outer.custom_size, Some(inner + 6.0));
+    assert_eq!(outer.color, Color::WHITE);
+    assert!(world.get::<Transform>(outline).unwrap().translation.z < 0.0);
+    assert!(world.get::<MyMarker>(outlined).is_some());
+    assert!(world.get::<MyMarker>(outline).is_none());
+}
+
+#[test]
+fn square_sprite_bundle_ignores_border() {
+    let mut world = World::new();
+    let entity = world.run_system_once(|mut commands: Commands| {
+        let sprite = SquareSprite::builder()
+            .size(40.0)
+            .border(3.0, Color::WHITE)
+            .build();
+        commands.spawn(square_sprite(sprite)).id()
+    });
+    assert!(world.get::<Children>(entity).is_none());
+    assert_eq!(
+        world.get::<Sprite>(entity).unwrap().custom_size,
+        Some(Vec2::splat(40.0))
+    );
+}
+
+fn spawn_test_menu(world: &mut World) -> (Entity, [Entity; 3]) {
+    world.run_system_once(|mut commands: Commands| {
+        let mut buttons = [Entity::PLACEHOLDER; 3];
+        let root = spawn_ui_root(&mut commands, JustifyContent::Center, AlignItems::Center)
+            .with_children(|parent| {
+                for (button, label) in buttons.iter_mut().zip(["Play", "Options", "Quit"]) {
+                    *button = spawn_button::<MyMarker>(parent, label, ButtonStyle::default());
+                }
+            })
+            .id();
+        (root, buttons)
+    })
+}
+
+fn press_menu_keys(world: &mut World, keys: &[KeyCode]) -> usize {
+    let mut input = world.resource_mut::<Input<KeyCode>>();
+    input.reset_all();
+    for key in keys {
+        input.press(*key);
+    }
+    world.run_system_once(menu_navigation_system::<MyMarker>);
+    world.resource::<MenuFocus<MyMarker>>().0
+}
+
+#[test]
+fn menu_navigation_wraps_focus() {
+    let mut world = World::new();
+    world.init_resource::<Input<KeyCode>>();
+    world.init_resource::<MenuFocus<MyMarker>>();
+    let (_, buttons) = spawn_test_menu(&mut world);
+    let press = press_menu_keys;
+    assert_eq!(press(&mut world, &[KeyCode::Down]), 1);
+    assert_eq!(press(&mut world, &[KeyCode::Tab]), 2);
+    assert_eq!(press(&mut world, &[KeyCode::Right]), 0);
+    assert_eq!(press(&mut world, &[KeyCode::Up]), 2);
+    assert_eq!(press(&mut world, &[KeyCode::ShiftLeft, KeyCode::Tab]), 1);
+
+    let style = ButtonStyle::default();
+    assert_eq!(
+        world.get::<BackgroundColor>(buttons[1]).unwrap().0,
+        style.hovered()
+    );
+    assert_eq!(
+        world.get::<BackgroundColor>(buttons[2]).unwrap().0,
+        style.background_color
+    );
+
+    assert_eq!(press(&mut world, &[KeyCode::Return]), 1);
+    assert_eq!(
+        world.get::<Interaction>(buttons[1]),
+        Some(&Interaction::Pressed)
+    );
+    assert_eq!(
+        world.get::<Interaction>(buttons[0]),
+        Some(&Interaction::None)
+    );
+}
+
+#[test]
+fn menu_navigation_follows_order_after_respawn() {
+    let mut world = World::new();
+    world.init_resource::<Input<KeyCode>>();
+    world.init_resource::<MenuFocus<MyMarker>>();
+    // leaving and re-entering a menu state recycles the entity ids
+    let (root, _) = spawn_test_menu(&mut world);
+    despawn_with_children_recursive(&mut world, root);
+    let (_, buttons) = spawn_test_menu(&mut world);
+    let focused = |world: &World| {
+        buttons
+            .iter()
+            .position(|button| {
+                world.get::<BackgroundColor>(*button).unwrap().0 == ButtonStyle::default().hovered()
+            })
+            .unwrap()
+    };
+    for expected in [1, 2, 0] {
+        assert_eq!(press_menu_keys(&mut world, &[KeyCode::Down]), expected);
+        assert_eq!(focused(&world), expected);
+    }
+}
+
+#[derive(Resource, Debug)]
+struct LeveledUp;
+
+#[test]
+fn transient_resource_expires() {
+    let mut app = App::new();
+    app.add_plugins(TransientResourcePlugin);
+    app.world
+        .run_system_once(|mut flag: ResourceHandle<LeveledUp>| {
+            flag.insert_transient(LeveledUp, 2);
+        });
+    let mut present = Vec::new();
+    for _ in 0..4 {
+        app.update();
+        present.push(app.world.contains_resource::<LeveledUp>());
+    }
+    assert_eq!(present, [true, true, false, false]);
+}
+
+#[test]
+fn transient_resource_reinsert_restarts() {
+    let mut app = App::new();
+    app.add_plugins(TransientResourcePlugin);
+    let insert = |app: &mut App| {
+        app.world
+            .run_system_once(|mut flag: ResourceHandle<LeveledUp>| {
+                flag.insert_transient(LeveledUp, 1);
+            });
+    };
+    insert(&mut app);
+    app.update();
+    insert(&mut app);
+    app.update();
+    assert!(app.world.contains_resource::<LeveledUp>());
+    app.update();
+    assert!(!app.world.contains_resource::<LeveledUp>());
+}
+
+#[test]
+fn spawn_default_collect_returns_ids() {
+    let mut world = World::new();
+    let entities = world
+        .run_system_once(|mut spawner: EntitySpawner<MyMarker>| spawner.spawn_default_collect(4));
+    assert_eq!(entities.len(), 4);
+    for entity in entities {
+        assert!(world.get::<MyMarker>(entity).is_some());
+    }
+    assert_eq!(count::<With<MyMarker>>(&mut world), 4);
+}
+
+#[test]
+fn forbid_component_despawns_combination() {
+    let mut world = World::new();
+    let forbidden = world.spawn((TagA, TagB)).id();
+    let allowed = world.spawn(TagA).id();
+    let other = world.spawn(TagB).id();
+    world.run_system_once(forbid_component_system::<TagA, With<TagB>>(
+        ForbidPolicy::Despawn,
+    ));
+    assert!(world.get_entity(forbidden).is_none());
+    assert!(world.get_entity(allowed).is_some());
+    assert!(world.get_entity(other).is_some());
+
+    world.run_system_once(forbid_component_system::<TagA, With<TagB>>(
+        ForbidPolicy::Warn,
+    ));
+    assert!(world.get_entity(allowed).is_some());
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "forbidden component")]
+fn forbid_component_panics_in_debug() {
+    let mut world = World::new();
+    world.spawn((TagA, TagC));
+    world.run_system_once(forbid_component_system::<TagC, ()>(ForbidPolicy::Panic));
+}
+
+#[test]
+fn fade_out_then_despawn() {
+    let mut app = App::new();
+    app.init_resource::<Time>()
+        .add_systems(Update, fade_out_system);
+    let sprite = app
+        .world
+        .spawn((
+            square_sprite(SquareSprite {
+                color: Color::rgba(1.0, 0.0, 0.0, 0.8),
+                ..Default::default()
+            }),
+            FadeOut::new(Duration::from_millis(400)),
+        ))
+        .id();
+    let alpha = |app: &App| app.world.get::<Sprite>(sprite).unwrap().color.a();
+    advance_time(&mut app, 100);
+    assert!((alpha(&app) - 0.6).abs() < 1e-5);
+    advance_time(&mut app, 200);
+    assert!((alpha(&app) - 0.2).abs() < 1e-5);
+    assert_eq!(app.world.get::<Sprite>(sprite).unwrap().color.r(), 1.0);
+    advance_time(&mut app, 100);
+    assert!(app.world.get_entity(sprite).is_none());
+}
+
+#[test]
+fn spawn_default_batch_under_parent() {
+    let mut world = World::new();
+    let board = world.spawn(TagA).id();
+    let cells = world.run_system_once(move |mut spawner: EntitySpawner<MyMarker>| {
+        spawner.spawn_default_batch_under(board, 9)
+    });
+    let children = world.get::<Children>(board).unwrap();
+    assert_eq!(children.len(), 9);
+    assert_eq!(children.to_vec(), cells);
+    assert_eq!(count::<With<MyMarker>>(&mut world), 9);
+}
+
+#[test]
+fn startup_closure_plugin_runs_once() {
+    let mut app = App::new();
+    app.init_resource::<Score>()
+        .add_plugins(startup_closure_plugin(
+            |mut commands: Commands, mut score: ResMut<Score>| {
+                score.0 += 1;
+                commands.spawn(MyMarker);
+            },
+        ));
+    app.update();
+    assert_eq!(app.world.resource::<Score>(), &Score(1));
+    app.update();
+    app.update();
+    assert_eq!(app.world.resource::<Score>(), &Score(1));
+    assert_eq!(count::<With<MyMarker>>(&mut app.world), 1);
+}
+
+#[test]
+fn palette_known_id() {
+    let palette = Palette::new(Color::FUCHSIA)
+        .with(0, Color::BLACK)
+        .with(1, Color::GREEN);
+    let tile = square_sprite_from_palette(&palette, 1, 2.0, 3.0, 16.0);
+    assert_eq!(tile.sprite.color, Color::GREEN);
+    assert_eq!(tile.sprite.custom_size, Some(Vec2::splat(16.0)));
+    assert_eq!(tile.transform.translation, Vec3::new(32.0, 48.0, 0.0));
+}
+
+#[test]
+fn palette_unknown_id_falls_back() {
+    let mut palette = Palette::new(Color::FUCHSIA).with(0, Color::BLACK);
+    let tile = square_sprite_from_palette(&palette, 7, 0.0, 0.0, 16.0);
+    assert_eq!(tile.sprite.color, Color::FUCHSIA);
+    palette.fallback = Color::GRAY;
+    assert_eq!(palette.get(7), Color::GRAY);
+    assert_eq!(palette.get(0), Color::BLACK);
+}
+
+#[test]
+fn debounced_button_clicks_once_per_window() {
+    let mut app = App::new();
+    app.init_resource::<Time>()
+        .add_event::<ButtonClicked<MyMarker>>()
+        .add_systems(
+            Update,
+            (
+                button_debounce_system,
+                button_click_event_system::<MyMarker>,
+            )
+                .chain(),
+        );
+    let button = spawn_test_button(&mut app.world, ButtonStyle::default());
+    app.world
+        .entity_mut(button)
+        .insert(ButtonDebounce::new(Duration::from_millis(500)));
+    let mut reader = app
+        .world
+        .resource::<Events<ButtonClicked<MyMarker>>>()
+        .get_reader();
+    let mut clicks = 0;
+    for (interaction, millis) in [
+        (Interaction::Pressed, 10),
+        (Interaction::None, 100),
+        (Interaction::Pressed, 100),
+        (Interaction::None, 100),
+        (Interaction::Pressed, 100),
+        (Interaction::None, 100),
+        (Interaction::Pressed, 200),
+    ] {
+        *app.world.get_mut::<Interaction>(button).unwrap() = interaction;
+        advance_time(&mut app, millis);
+        clicks += reader
+            .read(app.world.resource::<Events<ButtonClicked<MyMarker>>>())
+            .count();
+    }
+    // rapid presses within 500ms only register the first; the last press is after the window
+    assert_eq!(clicks, 2);
+}
+
+#[test]
+fn count_tracker_follows_entities() {
+    let mut app = App::new();
+    app.add_plugins(CountTrackerPlugin::<MyMarker>::default());
+    let tracked = |app: &App| app.world.resource::<TrackedCount<MyMarker>>().0;
+    app.update();
+    assert_eq!(tracked(&app), 0);
+
+    let spawned: Vec<Entity> = app.world.spawn_batch([MyMarker; 3]).collect();
+    app.world.spawn(TagA);
+    app.update();
+    assert_eq!(tracked(&app), 3);
+
+    app.world.despawn(spawned[0]);
+    app.world.despawn(spawned[1]);
+    app.update();
+    assert_eq!(tracked(&app), 1);
+}
+
+#[test]
+fn marker_components_enum_inserts_marker() {
+    fn has_terrain(world: &World, entity: Entity) -> [bool; 3] {
+        [
+            world.get::<Grass>(entity).is_some(),
+            world.get::<Water>(entity).is_some(),
+            world.get::<Rock>(entity).is_some(),
+        ]
+    }
+    let mut world = World::new();
+    assert_eq!(TerrainKind::ALL.len(), 3);
+    for (index, &kind) in TerrainKind::ALL.iter().enumerate() {
+        let entity = world.run_system_once_with(
+            kind,
+            |In(kind): In<TerrainKind>, mut commands: Commands| {
+                let mut entity = commands.spawn_empty();
+                kind.insert(&mut entity);
+                entity.id()
+            },
+        );
+        let mut expected = [false; 3];
+        expected[index] = true;
+        assert_eq!(has_terrain(&world, entity), expected);
+
+        world.run_system_once_with(
+            (kind, entity),
+            |In((kind, entity)): In<(TerrainKind, Entity)>, mut commands: Commands| {
+                kind.remove(&mut commands.entity(entity));
+            },
+        );
+        assert_eq!(has_terrain(&world, entity), [false; 3]);
+    }
+}
+
+#[derive(Resource)]
+struct Slot(Entity);
+
+#[test]
+fn despawn_set_runs_after_frame() {
+    let mut app = App::new();
+    app.add_plugins(DespawnSetPlugin);
+    let first = app.world.spawn(MyMarker).id();
+    app.world.insert_resource(Slot(first));
+    app.add_systems(
+        Update,
+        |mut slot: ResMut<Slot>,
+         mut spawner: EntitySpawner<MyMarker>,
+         mut despawner: EntityDespawner| {
+            despawner.despawn_deferred(slot.0);
+            slot.0 = spawner.spawn_default().id();
+        },
+    )
+    .add_systems(PostUpdate, move |query: Query<Entity, With<MyMarker>>| {
+        // the replaced entity is still around until `DespawnSet`
+        assert_eq!(query.iter().count(), 2);
+    });
+    app.update();
+    let second = app.world.resource::<Slot>().0;
+    assert!(app.world.get_entity(first).is_none());
+    assert!(app.world.get_entity(second).is_some());
+    app.update();
+    assert!(app.world.get_entity(second).is_none());
+    assert_eq!(count::<With<MyMarker>>(&mut app.world), 1);
+}
+
+/// A tiny xorshift generator standing in for a real RNG
+#[derive(Resource)]
+struct TestRng(u64);
+
+impl From<u64> for TestRng {
+    fn from(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+}
+
+impl TestRng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+#[test]
+fn seeded_rng_is_reproducible() {
+    let first_value = |seed| {
+        let mut app = App::new();
+        app.add_plugins(seeded_rng_plugin::<TestRng>(seed));
+        app.update();
+        app.world.resource_mut::<TestRng>().next()
+    };
+    assert_eq!(first_value(42), first_value(42));
+    assert_ne!(first_value(42), first_value(7));
+}
+
+#[test]
+fn resource_handle_modify_if() {
+    let mut world = World::new();
+    world.insert_resource(Score(1));
+    let decrement = |world: &mut World| {
+        world.run_system_once(|mut score: ResourceHandle<Score>| {
+            score.modify_if(|score| score.0 > 0, |score| score.0 -= 1);
+        });
+    };
+    decrement(&mut world);
+    assert_eq!(world.resource::<Score>(), &Score(0));
+    decrement(&mut world);
+    assert_eq!(world.resource::<Score>(), &Score(0));
+}
+
+#[test]
+fn toggle_state_with_key() {
+    let mut app = App::new();
+    app.add_state::<GameState>()
+        .init_resource::<Input<KeyCode>>()
+        .add_plugins(toggle_state_plugin(
+            GameState::Playing,
+            GameState::Paused,
+            KeyCode::Escape,
+        ));
+    let press_escape = |app: &mut App| {
+        let mut input = app.world.resource_mut::<Input<KeyCode>>();
+        input.press(KeyCode::Escape);
+        app.update();
+        // the transition is applied at the start of the next update
+        app.world.resource_mut::<Input<KeyCode>>().reset_all();
+        app.update();
+        *app.world.resource::<State<GameState>>().get()
+    };
+    // not in either toggled state, so nothing happens
+    assert_eq!(press_escape(&mut app), GameState::Menu);
+
+    set_state(&mut app, GameState::Playing);
+    assert_eq!(press_escape(&mut app), GameState::Paused);
+    assert_eq!(press_escape(&mut app), GameState::Playing);
+    assert_eq!(press_escape(&mut app), GameState::Paused);
+}
+
+#[test]
+fn square_sprite_with_scale() {
+    let sprite = SquareSprite {
+        x: 2.0,
+        y: -1.0,
+        z: 5.0,
+        size: 10.0,
+        grid: 12.0,
+        border: Some((1.0, Color::WHITE)),
+        ..Default::default()
+    };
+    let scaled = sprite.clone().with_scale(2.0);
+    assert_eq!(scaled.size, 20.0);
+    assert_eq!(scaled.border, Some((2.0, Color::WHITE)));
+    assert_eq!(
+        scaled.translation(),
+        sprite.translation().truncate().extend(0.0) * 2.0 + Vec3::Z * 5.0
+    );
+    let bundle = square_sprite(scaled);
+    assert_eq!(bundle.sprite.custom_size, Some(Vec2::splat(20.0)));
+    assert_eq!(bundle.transform.translation, Vec3::new(48.0, -24.0, 5.0));
+}
+
+struct Cells;
+
+#[test]
+fn collect_entities_into_list() {
+    let mut app = App::new();
+    app.add_plugins((
+        CollectPlugin::<MyMarker>::default(),
+        CollectPlugin::<TagA, Cells>::default(),
+    ));
+    let mut markers: Vec<Entity> = app.world.spawn_batch([MyMarker; 3]).collect();
+    markers.sort();
+    let cell = app.world.spawn(TagA).id();
+    app.update();
+    assert_eq!(app.world.resource::<EntityList<MyMarker>>().0, markers);
+    assert_eq!(app.world.resource::<EntityList<Cells>>().0, vec![cell]);
+
+    app.world.despawn(markers[1]);
+    app.update();
+    assert_eq!(
+        app.world.resource::<EntityList<MyMarker>>().0,
+        vec![markers[0], markers[2]]
+    );
+}
+
+#[derive(Component)]
+struct Armor(i32);
+
+#[test]
+fn despawn_where_predicate() {
+    let mut world = World::new();
+    let entities: Vec<Entity> = [-3, 0, 2, 7]
+        .into_iter()
+        .map(|armor| world.spawn(Armor(armor)).id())
+        .collect();
+    world.run_system_once(despawn_where_system(|armor: &Armor| armor.0 <= 0));
+    let alive: Vec<bool> = entities
+        .iter()
+        .map(|&entity| world.get_entity(entity).is_some())
+        .collect();
+    assert_eq!(alive, [false, false, true, true]);
+}
+
+#[test]
+fn button_style_padding() {
+    let mut world = World::new();
+    let padding = UiRect::axes(Val::Px(12.0), Val::Px(6.0));
+    let button = spawn_test_button(
+        &mut world,
+        ButtonStyle {
+            padding,
+            ..Default::default()
+        },
+    );
+    assert_eq!(world.get::<Style>(button).unwrap().padding, padding);
+
+    let default = spawn_test_button(&mut world, ButtonStyle::default());
+    assert_eq!(
+        world.get::<Style>(default).unwrap().padding,
+        UiRect::all(Val::Px(4.0))
+    );
+
+    world.run_system_once(move |mut commands: Commands| {
+        restyle_button(&mut commands, default, ButtonStyle::small());
+    });
+    assert_eq!(
+        world.get::<Style>(default).unwrap().padding,
+        ButtonStyle::small().padding
+    );
+}
+
+#[test]
+fn reset_plugin_starts_new_game() {
+    let mut app = App::new();
+    app.insert_resource(Score(12))
+        .insert_resource(DoubleScore(24))
+        .add_plugins(ResetPlugin::new().resource::<Score>());
+    let resettable = app.world.spawn((MyMarker, Resettable)).id();
+    let child = app.world.spawn(TagA).set_parent(resettable).id();
+    let kept = app.world.spawn(MyMarker).id();
+    app.update();
+    assert!(app.world.get_entity(resettable).is_some());
+
+    app.world.send_event(ResetGame);
+    app.update();
+    assert!(app.world.get_entity(resettable).is_none());
+    assert!(app.world.get_entity(child).is_none());
+    assert!(app.world.get_entity(kept).is_some());
+    assert_eq!(app.world.resource::<Score>(), &Score::default());
+    assert_eq!(app.world.resource::<DoubleScore>(), &DoubleScore(24));
+
+    // each event is only handled once
+    app.world.resource_mut::<Score>().0 = 3;
+    app.update();
+    assert_eq!(app.world.resource::<Score>(), &Score(3));
+}